@@ -1,8 +1,10 @@
 use alloc::borrow::Cow;
 use alloc::vec::Vec;
 
+use crate::encoder::{EncodingError, EncodingFormatError};
 #[cfg(feature = "color_quant")]
-use alloc::collections::{BTreeMap, BTreeSet};
+use crate::quantize::NeuQuant;
+use crate::quantize::{FrameOptions, Quantizer};
 
 /// Disposal method
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -132,6 +134,124 @@ impl Extension {
     }
 }
 
+/// Pixel aspect ratio as stored in the logical screen descriptor.
+///
+/// The encoded value `n` describes pixels with a width to height ratio of `(n + 15) / 64`, which
+/// covers ratios from 1:4 to about 4.2:1 in steps of 1/64. The value `0` means no aspect ratio
+/// information is given, in which case pixels are assumed to be square.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PixelAspectRatio(pub u8);
+
+impl PixelAspectRatio {
+    /// No aspect ratio information is given.
+    pub const UNSPECIFIED: Self = Self(0);
+    /// Square pixels, explicitly encoded.
+    pub const SQUARE: Self = Self(49);
+
+    /// Width divided by height of a pixel, or `None` if unspecified.
+    #[must_use]
+    pub fn to_f32(self) -> Option<f32> {
+        if self.0 == 0 {
+            None
+        } else {
+            Some((f32::from(self.0) + 15.) / 64.)
+        }
+    }
+
+    /// Encodes the closest representable ratio of pixel width to height.
+    ///
+    /// Returns `None` if `ratio` is not within the representable range.
+    #[must_use]
+    pub fn from_f32(ratio: f32) -> Option<Self> {
+        let n = ratio * 64. - 15.;
+        // `n` is NaN if `ratio` was, failing both comparisons
+        if n >= 0.5 && n < 255.5 {
+            // `as` rounds towards zero, so add one half to round to nearest
+            Some(Self((n + 0.5) as u8))
+        } else {
+            None
+        }
+    }
+}
+
+/// Contents of a Plain Text extension.
+///
+/// The text is meant to be rendered in a grid of character cells, using a monospaced font of the
+/// decoder's choosing. Colors are indices into the global color table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlainText {
+    /// Offset of the text grid from the left border of the canvas.
+    pub left: u16,
+    /// Offset of the text grid from the top border of the canvas.
+    pub top: u16,
+    /// Width of the text grid in pixels.
+    pub width: u16,
+    /// Height of the text grid in pixels.
+    pub height: u16,
+    /// Width of each character cell in pixels.
+    pub cell_width: u8,
+    /// Height of each character cell in pixels.
+    pub cell_height: u8,
+    /// Index of the text color in the global color table.
+    pub foreground: u8,
+    /// Index of the background color in the global color table.
+    pub background: u8,
+    /// The text, with all sub-blocks concatenated.
+    ///
+    /// Characters are placed into cells from left to right and top to bottom.
+    pub text: Vec<u8>,
+}
+
+impl PlainText {
+    /// Length of the header sub-block preceding the text.
+    pub(crate) const HEADER_LEN: usize = 12;
+
+    /// Parses the header sub-block, leaving the text empty.
+    pub(crate) fn from_header(header: &[u8]) -> Option<Self> {
+        let header: &[u8; Self::HEADER_LEN] = header.try_into().ok()?;
+        let le = |i: usize| u16::from_le_bytes([header[i], header[i + 1]]);
+        Some(Self {
+            left: le(0),
+            top: le(2),
+            width: le(4),
+            height: le(6),
+            cell_width: header[8],
+            cell_height: header[9],
+            foreground: header[10],
+            background: header[11],
+            text: Vec::new(),
+        })
+    }
+}
+
+/// Channel order and alpha representation of true color pixels with alpha.
+///
+/// Used by [`Frame::from_rgba_with_format`] and [`Frame::from_rgba16_with_format`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RgbaFormat {
+    /// Red, green, blue and alpha.
+    Rgba,
+    /// Blue, green, red and alpha.
+    Bgra,
+    /// Red, green, blue and alpha, with the colors premultiplied by alpha.
+    PremultipliedRgba,
+    /// Blue, green, red and alpha, with the colors premultiplied by alpha.
+    PremultipliedBgra,
+}
+
+impl RgbaFormat {
+    #[inline]
+    fn is_bgra(self) -> bool {
+        matches!(self, Self::Bgra | Self::PremultipliedBgra)
+    }
+
+    #[inline]
+    fn is_premultiplied(self) -> bool {
+        matches!(self, Self::PremultipliedRgba | Self::PremultipliedBgra)
+    }
+}
+
 /// A GIF frame
 #[derive(Debug, Clone)]
 pub struct Frame<'a> {
@@ -178,6 +298,17 @@ impl Default for Frame<'_> {
     }
 }
 
+impl Frame<'_> {
+    /// Removes unused entries from the local palette and merges duplicate colors.
+    ///
+    /// The buffer and transparent index are remapped to the smaller palette, which also reduces
+    /// the LZW code size when encoding. Frames without a local palette are not changed, use
+    /// [`optimize_palettes`](crate::optimize::optimize_palettes) for whole animations.
+    pub fn optimize_palette(&mut self) {
+        crate::optimize::optimize_local_palette(self);
+    }
+}
+
 impl Frame<'static> {
     /// Creates a frame from pixels in RGBA format.
     ///
@@ -193,7 +324,21 @@ impl Frame<'static> {
     #[cfg(feature = "color_quant")]
     #[track_caller]
     pub fn from_rgba(width: u16, height: u16, pixels: &mut [u8]) -> Self {
-        Frame::from_rgba_speed(width, height, pixels, 1)
+        unwrap_frame(Frame::try_from_rgba(width, height, pixels))
+    }
+
+    /// Creates a frame from pixels in RGBA format, like [`from_rgba`](Self::from_rgba).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 4`.
+    #[cfg(feature = "color_quant")]
+    pub fn try_from_rgba(
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+    ) -> Result<Self, EncodingError> {
+        Frame::try_from_rgba_speed(width, height, pixels, 1)
     }
 
     /// Creates a frame from pixels in RGBA format.
@@ -213,77 +358,174 @@ impl Frame<'static> {
     #[cfg(feature = "color_quant")]
     #[track_caller]
     pub fn from_rgba_speed(width: u16, height: u16, pixels: &mut [u8], speed: i32) -> Self {
-        assert_eq!(width as usize * height as usize * 4, pixels.len(), "Too much or too little pixel data for the given width and height to create a GIF Frame");
-        assert!(
-            speed >= 1 && speed <= 30,
-            "speed needs to be in the range [1, 30]"
-        );
-        let mut transparent: Option<[u8; 4]> = None;
-        for pix in pixels.chunks_exact_mut(4) {
-            if pix[3] != 0 {
-                pix[3] = 0xFF;
-                continue;
-            }
+        unwrap_frame(Frame::try_from_rgba_speed(width, height, pixels, speed))
+    }
 
-            if let Some([r, g, b, a]) = transparent {
-                pix[0] = r;
-                pix[1] = g;
-                pix[2] = b;
-                pix[3] = a;
-            } else {
-                transparent = Some([pix[0], pix[1], pix[2], pix[3]]);
-            }
+    /// Creates a frame from pixels in RGBA format, like [`from_rgba_speed`](Self::from_rgba_speed).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 4`.
+    /// *   [`EncodingFormatError::InvalidSpeed`] if `speed < 1` or `speed > 30`.
+    #[cfg(feature = "color_quant")]
+    pub fn try_from_rgba_speed(
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+        speed: i32,
+    ) -> Result<Self, EncodingError> {
+        if speed < 1 || speed > 30 {
+            return Err(EncodingFormatError::InvalidSpeed.into());
         }
+        Frame::try_from_rgba_with_quantizer(width, height, pixels, &NeuQuant::new(speed))
+    }
+
+    /// Creates a frame from pixels in RGBA format, reducing the colors with `quantizer`.
+    ///
+    /// This is a lossy method. The `gif` format does not support arbitrary alpha but only a 1-bit
+    /// transparency mask per pixel. Any non-zero alpha value will be interpreted as a fully opaque
+    /// pixel. Additionally, only 256 colors can appear in a single frame. If the image has more
+    /// colors, the palette is computed by the [`Quantizer`] and each pixel is mapped to the
+    /// nearest color, with a separate palette entry for transparent pixels. Use
+    /// [`FrameOptions::quantize_rgba`] for dithering and alpha handling.
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    #[track_caller]
+    pub fn from_rgba_with_quantizer<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+        quantizer: &Q,
+    ) -> Self {
+        unwrap_frame(Frame::try_from_rgba_with_quantizer(
+            width, height, pixels, quantizer,
+        ))
+    }
+
+    /// Creates a frame from pixels in RGBA format, like
+    /// [`from_rgba_with_quantizer`](Self::from_rgba_with_quantizer).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 4`.
+    pub fn try_from_rgba_with_quantizer<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+        quantizer: &Q,
+    ) -> Result<Self, EncodingError> {
+        FrameOptions::new().try_quantize_rgba(width, height, pixels, quantizer)
+    }
 
-        // Attempt to build a palette of all colors. If we go over 256 colors,
-        // switch to the NeuQuant algorithm.
-        let mut colors: BTreeSet<(u8, u8, u8, u8)> = BTreeSet::new();
-        for pixel in pixels.chunks_exact(4) {
-            if colors.insert((pixel[0], pixel[1], pixel[2], pixel[3])) && colors.len() > 256 {
-                // > 256 colours, let's use NeuQuant.
-                let nq = color_quant::NeuQuant::new(speed, 256, pixels);
-
-                return Frame {
-                    width,
-                    height,
-                    buffer: Cow::Owned(
-                        pixels
-                            .chunks_exact(4)
-                            .map(|pix| nq.index_of(pix) as u8)
-                            .collect(),
-                    ),
-                    palette: Some(nq.color_map_rgb()),
-                    transparent: transparent.map(|t| nq.index_of(&t) as u8),
-                    ..Frame::default()
-                };
+    /// Creates a frame from 8-bit pixels in the given channel order, reducing the colors with
+    /// `quantizer`.
+    ///
+    /// Premultiplied colors are divided by alpha first, then the frame is created as by
+    /// [`from_rgba_with_quantizer`](Self::from_rgba_with_quantizer).
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    #[must_use]
+    #[track_caller]
+    pub fn from_rgba_with_format<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        format: RgbaFormat,
+        quantizer: &Q,
+    ) -> Self {
+        unwrap_frame(Frame::try_from_rgba_with_format(
+            width, height, pixels, format, quantizer,
+        ))
+    }
+
+    /// Creates a frame from 8-bit pixels in the given channel order, like
+    /// [`from_rgba_with_format`](Self::from_rgba_with_format).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 4`.
+    /// *   [`EncodingError::OutOfMemory`] if the conversion can not be allocated.
+    pub fn try_from_rgba_with_format<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        format: RgbaFormat,
+        quantizer: &Q,
+    ) -> Result<Self, EncodingError> {
+        check_pixels_len(width, height, 4, pixels)?;
+        let mut rgba = try_collect_rgba(pixels.chunks_exact(4).map(|pixel| {
+            let [r, g, b, a] = reorder(format, [pixel[0], pixel[1], pixel[2], pixel[3]]);
+            if !format.is_premultiplied() {
+                return [r, g, b, a];
             }
-        }
+            let unpremultiply = |c: u8| match a {
+                0 => 0,
+                a => ((u32::from(c) * 255 + u32::from(a) / 2) / u32::from(a)).min(255) as u8,
+            };
+            [unpremultiply(r), unpremultiply(g), unpremultiply(b), a]
+        }))?;
+        Frame::try_from_rgba_with_quantizer(width, height, &mut rgba, quantizer)
+    }
 
-        // Palette size <= 256 elements, we can build an exact palette.
-        let mut colors_vec: Vec<(u8, u8, u8, u8)> = colors.into_iter().collect();
-        colors_vec.sort_unstable();
-        let palette = colors_vec
-            .iter()
-            .flat_map(|&(r, g, b, _a)| [r, g, b])
-            .collect();
-        let colors_lookup: BTreeMap<(u8, u8, u8, u8), u8> =
-            colors_vec.into_iter().zip(0..=255).collect();
-
-        let index_of = |pixel: &[u8]| {
-            colors_lookup
-                .get(&(pixel[0], pixel[1], pixel[2], pixel[3]))
-                .copied()
-                .unwrap_or(0)
-        };
+    /// Creates a frame from 16-bit pixels in the given channel order, reducing the colors with
+    /// `quantizer`.
+    ///
+    /// Premultiplied colors are divided by alpha at full precision, then all channels are rounded
+    /// to 8 bits. Pixels with an alpha that rounds to zero are transparent. The frame is then
+    /// created as by [`from_rgba_with_quantizer`](Self::from_rgba_with_quantizer).
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    #[must_use]
+    #[track_caller]
+    pub fn from_rgba16_with_format<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &[u16],
+        format: RgbaFormat,
+        quantizer: &Q,
+    ) -> Self {
+        unwrap_frame(Frame::try_from_rgba16_with_format(
+            width, height, pixels, format, quantizer,
+        ))
+    }
 
-        Frame {
-            width,
-            height,
-            buffer: Cow::Owned(pixels.chunks_exact(4).map(index_of).collect()),
-            palette: Some(palette),
-            transparent: transparent.map(|t| index_of(&t)),
-            ..Frame::default()
+    /// Creates a frame from 16-bit pixels in the given channel order, like
+    /// [`from_rgba16_with_format`](Self::from_rgba16_with_format).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 4`.
+    /// *   [`EncodingError::OutOfMemory`] if the conversion can not be allocated.
+    pub fn try_from_rgba16_with_format<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &[u16],
+        format: RgbaFormat,
+        quantizer: &Q,
+    ) -> Result<Self, EncodingError> {
+        if width as usize * height as usize * 4 != pixels.len() {
+            return Err(EncodingFormatError::InvalidFrameSize.into());
         }
+        let to_u8 = |c: u32| ((c * 255 + 0x7FFF) / 0xFFFF) as u8;
+        let mut rgba = try_collect_rgba(pixels.chunks_exact(4).map(|pixel| {
+            let [r, g, b, a] = reorder(format, [pixel[0], pixel[1], pixel[2], pixel[3]]);
+            let a = u32::from(a);
+            let unpremultiply = |c: u16| match a {
+                _ if !format.is_premultiplied() => u32::from(c),
+                0 => 0,
+                a => ((u32::from(c) * 0xFFFF + a / 2) / a).min(0xFFFF),
+            };
+            [
+                to_u8(unpremultiply(r)),
+                to_u8(unpremultiply(g)),
+                to_u8(unpremultiply(b)),
+                to_u8(a),
+            ]
+        }))?;
+        Frame::try_from_rgba_with_quantizer(width, height, &mut rgba, quantizer)
     }
 
     /// Creates a frame from pixels in LumaAlpha format (grayscale pixels with transparency).
@@ -295,8 +537,28 @@ impl Frame<'static> {
     ///
     /// # Panics:
     /// *   If the length of pixels does not equal `width * height * 2`.
+    #[track_caller]
     pub fn from_grayscale_with_alpha(width: u16, height: u16, pixels: &[u8]) -> Self {
-        assert_eq!(width as usize * height as usize * 2, pixels.len(), "Too much or too little pixel data for the given width and height to create a GIF Frame");
+        unwrap_frame(Frame::try_from_grayscale_with_alpha(width, height, pixels))
+    }
+
+    /// Creates a frame from pixels in LumaAlpha format, like
+    /// [`from_grayscale_with_alpha`](Self::from_grayscale_with_alpha).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 2`.
+    /// *   [`EncodingError::OutOfMemory`] if the buffer can not be allocated.
+    pub fn try_from_grayscale_with_alpha(
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+    ) -> Result<Self, EncodingError> {
+        check_pixels_len(width, height, 2, pixels)?;
+        let mut buffer = Vec::new();
+        buffer
+            .try_reserve_exact(pixels.len() / 2)
+            .map_err(|_| EncodingError::OutOfMemory)?;
 
         // Input is in LumaA format.
         // Count the occurrences of all the colors, then pick the least common color as alpha.
@@ -318,15 +580,15 @@ impl Frame<'static> {
         // If there were no fully transparent pixels, do not allocate a color to transparency in the GIF
         // and return immediately with the generic grayscale palette
         if num_transparent_pixels == 0 {
-            let stripped_alpha: Vec<u8> = pixels.chunks_exact(2).map(|pixel| pixel[0]).collect();
-            return Frame {
+            buffer.extend(pixels.chunks_exact(2).map(|pixel| pixel[0]));
+            return Ok(Frame {
                 width,
                 height,
-                buffer: Cow::Owned(stripped_alpha),
+                buffer: Cow::Owned(buffer),
                 palette: Some(grayscale_palette),
                 transparent: None,
                 ..Frame::default()
-            };
+            });
         }
 
         // Choose the color that will be our alpha color
@@ -351,29 +613,26 @@ impl Frame<'static> {
         };
 
         // Strip alpha and replace fully transparent pixels with the chosen color
-        let paletted: Vec<u8> = pixels
-            .chunks_exact(2)
-            .map(|pixel| {
-                let color = pixel[0];
-                let alpha = pixel[1];
-                if alpha == 0 {
-                    least_used_color
-                } else if color == least_used_color {
-                    replacement_color
-                } else {
-                    color
-                }
-            })
-            .collect();
+        buffer.extend(pixels.chunks_exact(2).map(|pixel| {
+            let color = pixel[0];
+            let alpha = pixel[1];
+            if alpha == 0 {
+                least_used_color
+            } else if color == least_used_color {
+                replacement_color
+            } else {
+                color
+            }
+        }));
 
-        Frame {
+        Ok(Frame {
             width,
             height,
-            buffer: Cow::Owned(paletted),
+            buffer: Cow::Owned(buffer),
             palette: Some(grayscale_palette),
             transparent: Some(least_used_color),
             ..Frame::default()
-        }
+        })
     }
 
     /// Creates a frame from a palette and indexed pixels.
@@ -389,26 +648,44 @@ impl Frame<'static> {
         palette: impl Into<Vec<u8>>,
         transparent: Option<u8>,
     ) -> Self {
+        unwrap_frame(Frame::try_from_palette_pixels(
+            width,
+            height,
+            pixels,
+            palette,
+            transparent,
+        ))
+    }
+
+    /// Creates a frame from a palette and indexed pixels, like
+    /// [`from_palette_pixels`](Self::from_palette_pixels).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height`.
+    /// *   [`EncodingFormatError::TooManyColors`] if the length of palette > `256 * 3`.
+    pub fn try_from_palette_pixels(
+        width: u16,
+        height: u16,
+        pixels: impl Into<Vec<u8>>,
+        palette: impl Into<Vec<u8>>,
+        transparent: Option<u8>,
+    ) -> Result<Self, EncodingError> {
         let pixels = pixels.into();
         let palette = palette.into();
-        assert_eq!(
-            width as usize * height as usize,
-            pixels.len(),
-            "Too many or too little pixels for the given width and height to create a GIF Frame"
-        );
-        assert!(
-            palette.len() <= 256 * 3,
-            "Too many palette values to create a GIF Frame"
-        );
+        check_pixels_len(width, height, 1, &pixels)?;
+        if palette.len() > 256 * 3 {
+            return Err(EncodingFormatError::TooManyColors.into());
+        }
 
-        Frame {
+        Ok(Frame {
             width,
             height,
             buffer: Cow::Owned(pixels),
             palette: Some(palette),
             transparent,
             ..Frame::default()
-        }
+        })
     }
 
     /// Creates a frame from indexed pixels in the global palette.
@@ -422,21 +699,37 @@ impl Frame<'static> {
         pixels: impl Into<Vec<u8>>,
         transparent: Option<u8>,
     ) -> Self {
+        unwrap_frame(Frame::try_from_indexed_pixels(
+            width,
+            height,
+            pixels,
+            transparent,
+        ))
+    }
+
+    /// Creates a frame from indexed pixels in the global palette, like
+    /// [`from_indexed_pixels`](Self::from_indexed_pixels).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height`.
+    pub fn try_from_indexed_pixels(
+        width: u16,
+        height: u16,
+        pixels: impl Into<Vec<u8>>,
+        transparent: Option<u8>,
+    ) -> Result<Self, EncodingError> {
         let pixels = pixels.into();
-        assert_eq!(
-            width as usize * height as usize,
-            pixels.len(),
-            "Too many or too little pixels for the given width and height to create a GIF Frame"
-        );
+        check_pixels_len(width, height, 1, &pixels)?;
 
-        Frame {
+        Ok(Frame {
             width,
             height,
             buffer: Cow::Owned(pixels),
             palette: None,
             transparent,
             ..Frame::default()
-        }
+        })
     }
 
     /// Creates a frame from pixels in RGB format.
@@ -453,7 +746,18 @@ impl Frame<'static> {
     #[must_use]
     #[track_caller]
     pub fn from_rgb(width: u16, height: u16, pixels: &[u8]) -> Self {
-        Frame::from_rgb_speed(width, height, pixels, 1)
+        unwrap_frame(Frame::try_from_rgb(width, height, pixels))
+    }
+
+    /// Creates a frame from pixels in RGB format, like [`from_rgb`](Self::from_rgb).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 3`.
+    /// *   [`EncodingError::OutOfMemory`] if the RGBA conversion can not be allocated.
+    #[cfg(feature = "color_quant")]
+    pub fn try_from_rgb(width: u16, height: u16, pixels: &[u8]) -> Result<Self, EncodingError> {
+        Frame::try_from_rgb_speed(width, height, pixels, 1)
     }
 
     /// Creates a frame from pixels in RGB format.
@@ -474,14 +778,62 @@ impl Frame<'static> {
     #[must_use]
     #[track_caller]
     pub fn from_rgb_speed(width: u16, height: u16, pixels: &[u8], speed: i32) -> Self {
-        assert_eq!(width as usize * height as usize * 3, pixels.len(), "Too much or too little pixel data for the given width and height to create a GIF Frame");
-        let mut vec: Vec<u8> = Vec::new();
-        vec.try_reserve_exact(pixels.len() + width as usize * height as usize)
-            .expect("OOM");
-        for v in pixels.chunks_exact(3) {
-            vec.extend_from_slice(&[v[0], v[1], v[2], 0xFF]);
-        }
-        Frame::from_rgba_speed(width, height, &mut vec, speed)
+        unwrap_frame(Frame::try_from_rgb_speed(width, height, pixels, speed))
+    }
+
+    /// Creates a frame from pixels in RGB format, like [`from_rgb_speed`](Self::from_rgb_speed).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 3`.
+    /// *   [`EncodingError::OutOfMemory`] if the RGBA conversion can not be allocated.
+    /// *   [`EncodingFormatError::InvalidSpeed`] if `speed < 1` or `speed > 30`.
+    #[cfg(feature = "color_quant")]
+    pub fn try_from_rgb_speed(
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        speed: i32,
+    ) -> Result<Self, EncodingError> {
+        check_pixels_len(width, height, 3, pixels)?;
+        Frame::try_from_rgba_speed(width, height, &mut rgb_to_rgba(pixels)?, speed)
+    }
+
+    /// Creates a frame from pixels in RGB format, reducing the colors with `quantizer`.
+    ///
+    /// This is a lossy method. In the `gif` format only 256 colors can appear in a single frame.
+    /// If the image has more colors, the palette is computed by the [`Quantizer`].
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 3`.
+    #[must_use]
+    #[track_caller]
+    pub fn from_rgb_with_quantizer<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        quantizer: &Q,
+    ) -> Self {
+        unwrap_frame(Frame::try_from_rgb_with_quantizer(
+            width, height, pixels, quantizer,
+        ))
+    }
+
+    /// Creates a frame from pixels in RGB format, like
+    /// [`from_rgb_with_quantizer`](Self::from_rgb_with_quantizer).
+    ///
+    /// # Errors:
+    /// *   [`EncodingFormatError::InvalidFrameSize`] if the length of pixels does not equal
+    ///     `width * height * 3`.
+    /// *   [`EncodingError::OutOfMemory`] if the RGBA conversion can not be allocated.
+    pub fn try_from_rgb_with_quantizer<Q: Quantizer + ?Sized>(
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        quantizer: &Q,
+    ) -> Result<Self, EncodingError> {
+        check_pixels_len(width, height, 3, pixels)?;
+        Frame::try_from_rgba_with_quantizer(width, height, &mut rgb_to_rgba(pixels)?, quantizer)
     }
 
     /// Leaves empty buffer and empty palette behind
@@ -503,6 +855,50 @@ impl Frame<'static> {
     }
 }
 
+fn rgb_to_rgba(pixels: &[u8]) -> Result<Vec<u8>, EncodingError> {
+    try_collect_rgba(pixels.chunks_exact(3).map(|v| [v[0], v[1], v[2], 0xFF]))
+}
+
+/// Swaps a pixel of the format to RGBA order.
+fn reorder<T: Copy>(format: RgbaFormat, [c0, c1, c2, a]: [T; 4]) -> [T; 4] {
+    if format.is_bgra() {
+        [c2, c1, c0, a]
+    } else {
+        [c0, c1, c2, a]
+    }
+}
+
+fn try_collect_rgba(
+    pixels: impl ExactSizeIterator<Item = [u8; 4]>,
+) -> Result<Vec<u8>, EncodingError> {
+    let mut vec: Vec<u8> = Vec::new();
+    vec.try_reserve_exact(pixels.len() * 4)
+        .map_err(|_| EncodingError::OutOfMemory)?;
+    vec.extend(pixels.flatten());
+    Ok(vec)
+}
+
+pub(crate) fn check_pixels_len(
+    width: u16,
+    height: u16,
+    bytes_per_pixel: usize,
+    pixels: &[u8],
+) -> Result<(), EncodingError> {
+    if width as usize * height as usize * bytes_per_pixel != pixels.len() {
+        return Err(EncodingFormatError::InvalidFrameSize.into());
+    }
+    Ok(())
+}
+
+/// Unwraps the result of a fallible constructor for the panicking variants.
+#[track_caller]
+pub(crate) fn unwrap_frame(result: Result<Frame<'static>, EncodingError>) -> Frame<'static> {
+    match result {
+        Ok(frame) => frame,
+        Err(err) => panic!("{err}"),
+    }
+}
+
 #[test]
 #[cfg(feature = "color_quant")]
 // Creating the `colors_lookup` hashmap in Frame::from_rgba_speed panics due to
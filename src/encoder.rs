@@ -6,9 +6,19 @@ use alloc::vec::Vec;
 
 use weezl::{encode::Encoder as LzwEncoder, BitOrder};
 
-use crate::common::{AnyExtension, Block, DisposalMethod, Extension, Frame};
+use crate::common::{
+    AnyExtension, Block, DisposalMethod, Extension, Frame, PixelAspectRatio, PlainText,
+};
 use crate::io::{self, Write};
+use crate::reader::RowOrder;
 use crate::traits::WriteBytesExt;
+use crate::Version;
+
+#[cfg(feature = "async")]
+mod async_encoder;
+
+#[cfg(feature = "async")]
+pub use self::async_encoder::AsyncEncoder;
 
 /// The image has incorrect properties, making it impossible to encode as a gif.
 #[derive(Debug)]
@@ -20,6 +30,21 @@ pub enum EncodingFormatError {
     MissingColorPalette,
     /// LZW data is not valid for GIF. This may happen when wrong buffer is given to `write_lzw_pre_encoded_frame`
     InvalidMinCodeSize,
+    /// XMP metadata contains a NUL byte, which would terminate the extension block early.
+    InvalidXmpMetadata,
+    /// A frame does not have the required size, or its buffer does not match the size.
+    InvalidFrameSize,
+    /// The quantization speed is not in the range [1, 30].
+    InvalidSpeed,
+    /// An extension was written, or a frame needs a graphic control extension, but the encoder
+    /// writes a GIF87a file.
+    RequiresGif89a,
+    /// A color index is too large for the color table of the frame.
+    InvalidColorIndex,
+    /// The color resolution is not in the range [1, 8].
+    InvalidColorResolution,
+    /// A [`FrameWriter`] was dropped before the frame was finished, the output is incomplete.
+    UnfinishedFrame,
 }
 
 impl core::error::Error for EncodingFormatError {}
@@ -33,6 +58,15 @@ impl fmt::Display for EncodingFormatError {
                 "the GIF format requires a color palette but none was given"
             ),
             Self::InvalidMinCodeSize => write!(fmt, "LZW data is invalid"),
+            Self::InvalidXmpMetadata => write!(fmt, "XMP metadata must not contain NUL bytes"),
+            Self::InvalidFrameSize => write!(fmt, "frame size is invalid"),
+            Self::InvalidSpeed => write!(fmt, "speed needs to be in the range [1, 30]"),
+            Self::RequiresGif89a => write!(fmt, "extensions require the GIF89a format"),
+            Self::InvalidColorIndex => write!(fmt, "color index is out of range of the palette"),
+            Self::InvalidColorResolution => {
+                write!(fmt, "color resolution needs to be in the range [1, 8]")
+            }
+            Self::UnfinishedFrame => write!(fmt, "a frame was not finished"),
         }
     }
 }
@@ -132,6 +166,10 @@ pub enum ExtensionData {
     },
     /// Sets the number of repetitions
     Repetitions(Repeat),
+    /// Suggests a buffer size in bytes for reading the data stream (NETSCAPE buffering extension).
+    BufferSize(u32),
+    /// Plain text to be rendered in a grid of character cells.
+    PlainText(PlainText),
 }
 
 impl ExtensionData {
@@ -159,12 +197,130 @@ impl ExtensionData {
     }
 }
 
+/// Fields of the logical screen descriptor that can be set with an [`EncoderBuilder`].
+#[derive(Clone, Copy, Debug)]
+struct ScreenDescriptor {
+    background_color: u8,
+    /// Bits per primary color, defaults to the size of the global palette
+    color_resolution: Option<u8>,
+    sorted: bool,
+    pixel_aspect_ratio: PixelAspectRatio,
+    /// `None` chooses the version automatically
+    version: Option<Version>,
+}
+
+impl Default for ScreenDescriptor {
+    fn default() -> Self {
+        Self {
+            background_color: 0,
+            color_resolution: None,
+            sorted: false,
+            pixel_aspect_ratio: PixelAspectRatio::UNSPECIFIED,
+            version: None,
+        }
+    }
+}
+
+/// Builds an [`Encoder`] with control over the logical screen descriptor.
+///
+/// Create it with [`EncoderBuilder::new`], configure it and call [`EncoderBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct EncoderBuilder {
+    width: u16,
+    height: u16,
+    global_palette: Vec<u8>,
+    screen: ScreenDescriptor,
+}
+
+impl EncoderBuilder {
+    /// Creates a builder for an image of the given size, without global palette.
+    #[must_use]
+    #[inline]
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            global_palette: Vec::new(),
+            screen: ScreenDescriptor::default(),
+        }
+    }
+
+    /// Sets the global color palette in the format `[r, g, b, ...]`.
+    pub fn set_global_palette(&mut self, palette: &[u8]) {
+        self.global_palette.clear();
+        self.global_palette.extend_from_slice(palette);
+    }
+
+    /// Sets the index of the background color in the global palette, `0` by default.
+    pub fn set_background_color(&mut self, index: u8) {
+        self.screen.background_color = index;
+    }
+
+    /// Sets the number of bits per primary color of the original image.
+    ///
+    /// By default, the number of bits needed to index the global palette is written. `bits` has
+    /// to be in the range `1..=8`, otherwise [`build`](Self::build) returns
+    /// [`EncodingFormatError::InvalidColorResolution`].
+    pub fn set_color_resolution(&mut self, bits: u8) {
+        self.screen.color_resolution = Some(bits);
+    }
+
+    /// Declares that the global palette is sorted by decreasing importance.
+    ///
+    /// The palette is written as given, it is not sorted by the encoder.
+    pub fn set_sorted(&mut self, sorted: bool) {
+        self.screen.sorted = sorted;
+    }
+
+    /// Declares non-square pixels, by default the pixel aspect ratio is unspecified.
+    pub fn set_pixel_aspect_ratio(&mut self, pixel_aspect_ratio: PixelAspectRatio) {
+        self.screen.pixel_aspect_ratio = pixel_aspect_ratio;
+    }
+
+    /// Sets the version written in the header, instead of choosing it automatically.
+    ///
+    /// GIF87a files are read by strict legacy decoders, but can not contain extensions. Graphic
+    /// control extensions with default values are omitted. Anything else that needs an extension
+    /// requires GIF89a: frames with a delay, transparency, user input flag or a disposal method
+    /// other than `Keep`, repetitions, comments and other extensions.
+    ///
+    /// By default, the encoder writes GIF87a unless one of these is used. As the header comes
+    /// first, the output is held back in memory until the first of them is written, or until the
+    /// encoder is finished. With an explicit `Version::V89a` everything is written right away.
+    /// With `Version::V87a` the encoder returns [`EncodingFormatError::RequiresGif89a`] for them.
+    pub fn set_version(&mut self, version: Version) {
+        self.screen.version = Some(version);
+    }
+
+    /// Creates the encoder, writing the header and the global palette.
+    pub fn build<W: Write>(&self, w: W) -> Result<Encoder<W>, EncodingError> {
+        if let Some(bits) = self.screen.color_resolution {
+            if !(1..=8).contains(&bits) {
+                return Err(EncodingFormatError::InvalidColorResolution.into());
+            }
+        }
+        Encoder::with_screen(
+            w,
+            self.width,
+            self.height,
+            &self.global_palette,
+            self.screen,
+        )
+    }
+}
+
 /// GIF encoder.
 pub struct Encoder<W: Write> {
     w: Option<W>,
-    global_palette: bool,
+    /// Size bits of the global color table, if there is one
+    global_palette: Option<u8>,
     width: u16,
     height: u16,
+    version: Version,
+    /// Output held back while the version is chosen automatically, starting with the header.
+    pending: Option<Vec<u8>>,
+    /// A `FrameWriter` was dropped without finishing its frame
+    unfinished_frame: bool,
     buffer: Vec<u8>,
 }
 
@@ -178,15 +334,32 @@ impl<W: Write> Encoder<W> {
         width: u16,
         height: u16,
         global_palette: &[u8],
+    ) -> Result<Self, EncodingError> {
+        let screen = ScreenDescriptor {
+            version: Some(Version::V89a),
+            ..ScreenDescriptor::default()
+        };
+        Self::with_screen(w, width, height, global_palette, screen)
+    }
+
+    fn with_screen(
+        w: W,
+        width: u16,
+        height: u16,
+        global_palette: &[u8],
+        screen: ScreenDescriptor,
     ) -> Result<Self, EncodingError> {
         Self {
             w: Some(w),
-            global_palette: false,
+            global_palette: None,
             width,
             height,
+            version: screen.version.unwrap_or(Version::V87a),
+            pending: screen.version.is_none().then(Vec::new),
+            unfinished_frame: false,
             buffer: Vec::new(),
         }
-        .write_global_palette(global_palette)
+        .write_global_palette(global_palette, screen)
     }
 
     /// Write an extension block that signals a repeat behaviour.
@@ -194,23 +367,59 @@ impl<W: Write> Encoder<W> {
         self.write_extension(ExtensionData::Repetitions(repeat))
     }
 
+    /// Write an extension block that suggests a buffer size in bytes for reading the data stream.
+    ///
+    /// This is the rarely used buffering sub-block of the `NETSCAPE2.0` extension.
+    pub fn set_buffer_size_hint(&mut self, size: u32) -> Result<(), EncodingError> {
+        self.write_extension(ExtensionData::BufferSize(size))
+    }
+
     /// Writes the global color palette.
-    fn write_global_palette(mut self, palette: &[u8]) -> Result<Self, EncodingError> {
+    fn write_global_palette(
+        mut self,
+        palette: &[u8],
+        screen: ScreenDescriptor,
+    ) -> Result<Self, EncodingError> {
         let mut flags = 0;
         flags |= 0b1000_0000;
         let (palette, padding, table_size) = Self::check_color_table(palette)?;
-        self.global_palette = !palette.is_empty();
+        self.global_palette = (!palette.is_empty()).then_some(table_size);
         flags |= table_size;
-        flags |= table_size << 4;
-        self.write_screen_desc(flags)?;
+        flags |= screen.color_resolution.map_or(table_size, |bits| bits - 1) << 4;
+        if screen.sorted {
+            flags |= 0b0000_1000;
+        }
+        self.write_screen_desc(flags, screen)?;
         Self::write_color_table(self.writer()?, palette, padding)?;
         Ok(self)
     }
 
     /// Writes a frame to the image.
     ///
+    /// The rows of `Frame.buffer` are in their natural order from top to bottom. If
+    /// `Frame.interlaced` is set, they are reordered into the four interlacing passes.
+    ///
     /// Note: This function also writes a control extension if necessary.
     pub fn write_frame(&mut self, frame: &Frame<'_>) -> Result<(), EncodingError> {
+        self.check_frame_buffer(frame)?;
+        self.write_frame_header(frame)?;
+        let data = frame.rows_in_file_order()?;
+        self.write_image_block(&data)
+    }
+
+    /// Writes a frame whose buffer is already in the order of the interlacing passes.
+    ///
+    /// Unlike [`Encoder::write_frame`], rows of interlaced frames are not reordered. For frames
+    /// that are not interlaced both functions are the same.
+    ///
+    /// Note: This function also writes a control extension if necessary.
+    pub fn write_pre_interlaced_frame(&mut self, frame: &Frame<'_>) -> Result<(), EncodingError> {
+        self.check_frame_buffer(frame)?;
+        self.write_frame_header(frame)?;
+        self.write_image_block(&frame.buffer)
+    }
+
+    fn check_frame_buffer(&self, frame: &Frame<'_>) -> Result<(), EncodingError> {
         if usize::from(frame.width)
             .checked_mul(usize::from(frame.height))
             .map_or(true, |size| frame.buffer.len() < size)
@@ -221,34 +430,69 @@ impl<W: Write> Encoder<W> {
             (frame.width > 0 && frame.height > 0) || frame.buffer.is_empty(),
             "the frame has 0 pixels, but non-empty buffer"
         );
-        self.write_frame_header(frame)?;
-        self.write_image_block(&frame.buffer)
+        Ok(())
     }
 
-    fn write_frame_header(&mut self, frame: &Frame<'_>) -> Result<(), EncodingError> {
-        self.write_extension(ExtensionData::new_control_ext(
-            frame.delay,
-            frame.dispose,
-            frame.needs_user_input,
-            frame.transparent,
-        ))?;
+    /// Starts writing a frame whose pixels are supplied row by row.
+    ///
+    /// Only the metadata of `frame` is used, its `buffer` is ignored. The returned
+    /// [`FrameWriter`] compresses rows as they are written, so the whole frame never has to be in
+    /// memory. [`FrameWriter::finish`] has to be called after the last row.
+    ///
+    /// Note: This function also writes a control extension if necessary.
+    pub fn frame_writer(&mut self, frame: &Frame<'_>) -> Result<FrameWriter<'_, W>, EncodingError> {
+        let table_size = self.write_frame_header(frame)?;
+        let min_code_size = (table_size + 1).max(2);
+        self.writer()?.write_le(min_code_size)?;
+        self.buffer.clear();
+        let mut rows = RowOrder::new(frame.height, frame.interlaced);
+        Ok(FrameWriter {
+            encoder: self,
+            finished: false,
+            lzw: LzwEncoder::new(BitOrder::Lsb, min_code_size),
+            width: usize::from(frame.width),
+            rows_left: if frame.width > 0 {
+                usize::from(frame.height)
+            } else {
+                0
+            },
+            next_row: rows.next(),
+            rows,
+        })
+    }
+
+    /// Writes the control extension and image descriptor, returns the size bits of the color
+    /// table used by the frame.
+    fn write_frame_header(&mut self, frame: &Frame<'_>) -> Result<u8, EncodingError> {
+        // Frames of GIF87a files are drawn on top of each other, like with `Keep`
+        let needs_control = frame.delay != 0
+            || !matches!(frame.dispose, DisposalMethod::Any | DisposalMethod::Keep)
+            || frame.needs_user_input
+            || frame.transparent.is_some();
+        if needs_control || self.version != Version::V87a {
+            self.write_extension(ExtensionData::new_control_ext(
+                frame.delay,
+                frame.dispose,
+                frame.needs_user_input,
+                frame.transparent,
+            ))?;
+        }
         let mut flags = 0;
         if frame.interlaced {
             flags |= 0b0100_0000;
         }
-        let palette = match frame.palette {
+        let (palette, table_size) = match frame.palette {
             Some(ref palette) => {
                 flags |= 0b1000_0000;
                 let (palette, padding, table_size) = Self::check_color_table(palette)?;
                 flags |= table_size;
-                Some((palette, padding))
-            }
-            None if self.global_palette => None,
-            _ => {
-                return Err(EncodingError::from(
-                    EncodingFormatError::MissingColorPalette,
-                ))
+                (Some((palette, padding)), table_size)
             }
+            None => (
+                None,
+                self.global_palette
+                    .ok_or(EncodingFormatError::MissingColorPalette)?,
+            ),
         };
         let writer = self.writer()?;
         writer.write_le(Block::Image as u8)?;
@@ -260,7 +504,7 @@ impl<W: Write> Encoder<W> {
         if let Some((palette, padding)) = palette {
             Self::write_color_table(writer, palette, padding)?;
         }
-        Ok(())
+        Ok(table_size)
     }
 
     fn write_image_block(&mut self, data: &[u8]) -> Result<(), EncodingError> {
@@ -270,33 +514,32 @@ impl<W: Write> Encoder<W> {
             .map_err(|_| EncodingError::OutOfMemory)?;
         lzw_encode(data, &mut self.buffer);
 
-        let writer = self.w.as_mut().ok_or(EncodingError::WriterNotFound)?;
+        let writer = Self::output(&mut self.w, &mut self.pending)?;
         Self::write_encoded_image_block(writer, &self.buffer)
     }
 
     fn write_encoded_image_block(
-        writer: &mut W,
+        writer: &mut dyn Write,
         data_with_min_code_size: &[u8],
     ) -> Result<(), EncodingError> {
         let (&min_code_size, data) = data_with_min_code_size.split_first().unwrap_or((&2, &[]));
         writer.write_le(min_code_size)?;
+        Self::write_sub_blocks(writer, data)?;
+        writer.write_le(0u8)?;
+        Ok(())
+    }
 
-        let mut iter = data.chunks_exact(0xFF);
-        for full_block in iter.by_ref() {
-            writer.write_le(0xFFu8)?;
-            writer.write_all(full_block)?;
+    /// Splits `data` into sub-blocks, without the terminating empty block.
+    fn write_sub_blocks(writer: &mut dyn Write, data: &[u8]) -> Result<(), EncodingError> {
+        for block in data.chunks(0xFF) {
+            writer.write_le(block.len() as u8)?;
+            writer.write_all(block)?;
         }
-        let last_block = iter.remainder();
-        if !last_block.is_empty() {
-            writer.write_le(last_block.len() as u8)?;
-            writer.write_all(last_block)?;
-        }
-        writer.write_le(0u8)?;
         Ok(())
     }
 
     fn write_color_table(
-        writer: &mut W,
+        writer: &mut dyn Write,
         table: &[u8],
         padding: usize,
     ) -> Result<(), EncodingError> {
@@ -325,6 +568,7 @@ impl<W: Write> Encoder<W> {
         if let Repetitions(Repeat::Finite(0)) = extension {
             return Ok(());
         }
+        self.check_extensions_allowed()?;
         let writer = self.writer()?;
         writer.write_le(Block::Extension as u8)?;
         match extension {
@@ -346,11 +590,78 @@ impl<W: Write> Encoder<W> {
                     Repeat::Infinite => 0u16,
                 })?;
             }
+            BufferSize(size) => {
+                writer.write_le(Extension::Application as u8)?;
+                writer.write_le(11u8)?;
+                writer.write_all(b"NETSCAPE2.0")?;
+                writer.write_le(5u8)?;
+                writer.write_le(2u8)?;
+                writer.write_le(size)?;
+            }
+            PlainText(text) => {
+                writer.write_le(Extension::Text as u8)?;
+                writer.write_le(12u8)?;
+                writer.write_le(text.left)?;
+                writer.write_le(text.top)?;
+                writer.write_le(text.width)?;
+                writer.write_le(text.height)?;
+                writer.write_le(text.cell_width)?;
+                writer.write_le(text.cell_height)?;
+                writer.write_le(text.foreground)?;
+                writer.write_le(text.background)?;
+                for chunk in text.text.chunks(0xFF) {
+                    writer.write_le(chunk.len() as u8)?;
+                    writer.write_all(chunk)?;
+                }
+            }
         }
         writer.write_le(0u8)?;
         Ok(())
     }
 
+    /// Writes a comment extension to the image.
+    ///
+    /// The text is split into sub-blocks of at most 255 bytes. The specification recommends
+    /// 7-bit ASCII text.
+    pub fn write_comment(&mut self, text: &[u8]) -> Result<(), EncodingError> {
+        self.write_raw_extension(Extension::Comment.into(), &[text])
+    }
+
+    /// Writes XMP metadata to the image.
+    ///
+    /// The packet is embedded as specified by the XMP standard: it is written without sub-block
+    /// framing, followed by a "magic trailer" that lets GIF decoders skip over it. This requires
+    /// the packet to be free of NUL bytes, which holds for UTF-8 encoded XMP.
+    ///
+    /// Metadata should be written before the first frame.
+    pub fn write_xmp_metadata(&mut self, xmp: &[u8]) -> Result<(), EncodingError> {
+        if xmp.contains(&0) {
+            return Err(EncodingFormatError::InvalidXmpMetadata.into());
+        }
+        self.check_extensions_allowed()?;
+        // Any length byte that a decoder reads within the trailer skips exactly to its end.
+        let trailer: [u8; 257] = core::array::from_fn(|i| match i {
+            0 => 1,
+            i => (256 - i) as u8,
+        });
+        let writer = self.writer()?;
+        writer.write_le(Block::Extension as u8)?;
+        writer.write_le(Extension::Application as u8)?;
+        writer.write_le(11u8)?;
+        writer.write_all(b"XMP DataXMP")?;
+        writer.write_all(xmp)?;
+        writer.write_all(&trailer)?;
+        writer.write_le(0u8)?;
+        Ok(())
+    }
+
+    /// Writes an ICC color profile to the image.
+    ///
+    /// The profile should be written before the first frame.
+    pub fn write_icc_profile(&mut self, icc: &[u8]) -> Result<(), EncodingError> {
+        self.write_raw_extension(Extension::Application.into(), &[b"ICCRGBG1012", icc])
+    }
+
     /// Writes a raw extension to the image.
     ///
     /// This method can be used to write an unsupported extension to the file. `func` is the extension
@@ -361,6 +672,7 @@ impl<W: Write> Encoder<W> {
         func: AnyExtension,
         data: &[&[u8]],
     ) -> Result<(), EncodingError> {
+        self.check_extensions_allowed()?;
         let writer = self.writer()?;
         writer.write_le(Block::Extension as u8)?;
         writer.write_le(func.0)?;
@@ -392,16 +704,38 @@ impl<W: Write> Encoder<W> {
         Self::write_encoded_image_block(writer, &frame.buffer)
     }
 
-    fn write_screen_desc(&mut self, flags: u8) -> Result<(), EncodingError> {
+    fn write_screen_desc(
+        &mut self,
+        flags: u8,
+        screen: ScreenDescriptor,
+    ) -> Result<(), EncodingError> {
         let width = self.width;
         let height = self.height;
+        let signature = match self.version {
+            Version::V87a => b"GIF87a",
+            Version::V89a => b"GIF89a",
+        };
         let writer = self.writer()?;
-        writer.write_all(b"GIF89a")?;
+        writer.write_all(signature)?;
         writer.write_le(width)?;
         writer.write_le(height)?;
         writer.write_le(flags)?;
-        writer.write_le(0u8)?;
-        writer.write_le(0u8)?;
+        writer.write_le(screen.background_color)?;
+        writer.write_le(screen.pixel_aspect_ratio.0)?;
+        Ok(())
+    }
+
+    /// Switches to GIF89a if the version is chosen automatically, and writes the held back
+    /// output.
+    fn check_extensions_allowed(&mut self) -> Result<(), EncodingError> {
+        if let Some(mut pending) = self.pending.take() {
+            pending[3..6].copy_from_slice(b"89a");
+            self.version = Version::V89a;
+            self.writer()?.write_all(&pending)?;
+        }
+        if self.version == Version::V87a {
+            return Err(EncodingFormatError::RequiresGif89a.into());
+        }
         Ok(())
     }
 
@@ -425,31 +759,173 @@ impl<W: Write> Encoder<W> {
 
     fn write_trailer(&mut self) -> Result<(), EncodingError> {
         self.writer()?.write_le(Block::Trailer as u8)?;
+        // Nothing needed GIF89a
+        if let Some(pending) = self.pending.take() {
+            self.writer()?.write_all(&pending)?;
+        }
         Ok(())
     }
 
     #[inline]
-    fn writer(&mut self) -> Result<&mut W, EncodingError> {
-        self.w.as_mut().ok_or(EncodingError::WriterNotFound)
+    fn writer(&mut self) -> Result<&mut dyn Write, EncodingError> {
+        if self.unfinished_frame {
+            return Err(EncodingFormatError::UnfinishedFrame.into());
+        }
+        Self::output(&mut self.w, &mut self.pending)
+    }
+
+    /// The held back output while the version is not known yet, otherwise the writer.
+    fn output<'a>(
+        w: &'a mut Option<W>,
+        pending: &'a mut Option<Vec<u8>>,
+    ) -> Result<&'a mut dyn Write, EncodingError> {
+        match pending {
+            Some(pending) => Ok(pending),
+            None => Ok(w.as_mut().ok_or(EncodingError::WriterNotFound)?),
+        }
     }
 }
 
 impl<W: Write> Drop for Encoder<W> {
     #[cfg(feature = "raii_no_panic")]
     fn drop(&mut self) {
-        if self.w.is_some() {
+        if self.w.is_some() && !self.unfinished_frame {
             let _ = self.write_trailer();
         }
     }
 
     #[cfg(not(feature = "raii_no_panic"))]
     fn drop(&mut self) {
-        if self.w.is_some() {
+        if self.w.is_some() && !self.unfinished_frame {
             self.write_trailer().unwrap();
         }
     }
 }
 
+/// Writes the pixels of a frame row by row, see [`Encoder::frame_writer`].
+///
+/// Rows are written in the order they are stored in the file. For interlaced frames that is pass
+/// by pass, [`FrameWriter::next_row`] tells which row is expected next. Full sub-blocks of
+/// compressed data are written as soon as they are available.
+///
+/// Dropping the writer without calling [`FrameWriter::finish`], or after an error, leaves an
+/// incomplete frame in the output. The [`Encoder`] then returns
+/// [`EncodingFormatError::UnfinishedFrame`] for any further output, and does not write the trailer
+/// when dropped.
+pub struct FrameWriter<'a, W: Write> {
+    encoder: &'a mut Encoder<W>,
+    finished: bool,
+    lzw: LzwEncoder,
+    width: usize,
+    rows_left: usize,
+    next_row: Option<usize>,
+    rows: RowOrder,
+}
+
+impl<W: Write> FrameWriter<'_, W> {
+    /// Index of the row that is expected next, `None` once all rows have been written.
+    ///
+    /// Rows of interlaced frames are expected in the order of the four interlacing passes.
+    #[inline]
+    pub fn next_row(&self) -> Option<u16> {
+        self.next_row
+            .filter(|_| self.rows_left > 0)
+            .map(|row| row as u16)
+    }
+
+    /// Writes a single row of color indices.
+    ///
+    /// Its length has to be the width of the frame.
+    #[inline]
+    pub fn write_row(&mut self, row: &[u8]) -> Result<(), EncodingError> {
+        self.write_rows(row)
+    }
+
+    /// Writes any number of consecutive rows of color indices.
+    ///
+    /// The length of `rows` has to be a multiple of the width of the frame, and it must not
+    /// contain more rows than are left in the frame.
+    pub fn write_rows(&mut self, rows: &[u8]) -> Result<(), EncodingError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        if self.width == 0
+            || rows.len() % self.width != 0
+            || rows.len() / self.width > self.rows_left
+        {
+            return Err(EncodingFormatError::InvalidFrameSize.into());
+        }
+        let mut data = rows;
+        while !data.is_empty() {
+            self.compress(&mut data)?;
+            self.write_full_sub_blocks()?;
+        }
+        let count = rows.len() / self.width;
+        self.rows_left -= count;
+        self.next_row = self.rows.nth(count - 1);
+        Ok(())
+    }
+
+    /// Compresses as much of `data` as fits into the free space of the buffer.
+    ///
+    /// `IntoVec::encode` of weezl doesn't return before the end code, so the output space is
+    /// managed here.
+    fn compress(&mut self, data: &mut &[u8]) -> Result<(), EncodingError> {
+        const CHUNK_SIZE: usize = 1 << 12;
+        let buffer = &mut self.encoder.buffer;
+        let start = buffer.len();
+        buffer
+            .try_reserve(CHUNK_SIZE)
+            .map_err(|_| EncodingError::OutOfMemory)?;
+        buffer.resize(start + CHUNK_SIZE, 0);
+        let result = self.lzw.encode_bytes(data, &mut buffer[start..]);
+        buffer.truncate(start + result.consumed_out);
+        *data = &data[result.consumed_in..];
+        match result.status {
+            Ok(_) => Ok(()),
+            Err(_) => Err(EncodingFormatError::InvalidColorIndex.into()),
+        }
+    }
+
+    /// Writes the compressed data that fills whole sub-blocks.
+    fn write_full_sub_blocks(&mut self) -> Result<(), EncodingError> {
+        let buffer = &mut self.encoder.buffer;
+        let full = buffer.len() - buffer.len() % 0xFF;
+        if full > 0 {
+            let writer = Encoder::output(&mut self.encoder.w, &mut self.encoder.pending)?;
+            Encoder::<W>::write_sub_blocks(writer, &buffer[..full])?;
+            buffer.drain(..full);
+        }
+        Ok(())
+    }
+
+    /// Completes the frame after all rows have been written.
+    pub fn finish(mut self) -> Result<(), EncodingError> {
+        if self.rows_left > 0 {
+            return Err(EncodingFormatError::InvalidFrameSize.into());
+        }
+        let buffer = &mut self.encoder.buffer;
+        let result = self.lzw.into_vec(buffer).encode_all(&[]);
+        if result.status.is_err() {
+            return Err(EncodingFormatError::InvalidColorIndex.into());
+        }
+        let writer = Encoder::output(&mut self.encoder.w, &mut self.encoder.pending)?;
+        Encoder::<W>::write_sub_blocks(writer, buffer)?;
+        writer.write_le(0u8)?;
+        buffer.clear();
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for FrameWriter<'_, W> {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.encoder.unfinished_frame = true;
+        }
+    }
+}
+
 /// Encodes the data into the provided buffer.
 ///
 /// The first byte is the minimum code size, followed by LZW data.
@@ -475,11 +951,52 @@ impl Frame<'_> {
     /// Replace frame's buffer with a LZW-compressed one for use with [`Encoder::write_lzw_pre_encoded_frame`].
     ///
     /// Frames can be compressed in any order, separately from the `Encoder`, which can be used to compress frames in parallel.
+    ///
+    /// Rows of interlaced frames are reordered like in [`Encoder::write_frame`].
+    ///
+    /// # Panics:
+    /// *   If the compressed buffer can't be allocated.
+    #[track_caller]
     pub fn make_lzw_pre_encoded(&mut self) {
+        if let Err(err) = self.try_make_lzw_pre_encoded() {
+            panic!("{err}");
+        }
+    }
+
+    /// Replace frame's buffer with a LZW-compressed one, like
+    /// [`make_lzw_pre_encoded`](Self::make_lzw_pre_encoded).
+    ///
+    /// # Errors:
+    /// *   [`EncodingError::OutOfMemory`] if the compressed buffer can't be allocated. The frame
+    ///     is unchanged.
+    pub fn try_make_lzw_pre_encoded(&mut self) -> Result<(), EncodingError> {
+        let data = self.rows_in_file_order()?;
         let mut buffer = Vec::new();
-        buffer.try_reserve(self.buffer.len() / 2).expect("OOM");
-        lzw_encode(&self.buffer, &mut buffer);
+        buffer
+            .try_reserve(data.len() / 2)
+            .map_err(|_| EncodingError::OutOfMemory)?;
+        lzw_encode(&data, &mut buffer);
         self.buffer = Cow::Owned(buffer);
+        Ok(())
+    }
+
+    /// Returns the buffer with the rows in the order they are stored in the file.
+    ///
+    /// A buffer that is too small for the frame is returned unchanged.
+    fn rows_in_file_order(&self) -> Result<Cow<'_, [u8]>, EncodingError> {
+        let width = usize::from(self.width);
+        let len = width * usize::from(self.height);
+        if !self.interlaced || self.height <= 1 || self.buffer.len() < len {
+            return Ok(Cow::Borrowed(&self.buffer));
+        }
+        let mut buffer = Vec::new();
+        buffer
+            .try_reserve_exact(len)
+            .map_err(|_| EncodingError::OutOfMemory)?;
+        for row in RowOrder::new(self.height, true) {
+            buffer.extend_from_slice(&self.buffer[row * width..][..width]);
+        }
+        Ok(Cow::Owned(buffer))
     }
 }
 
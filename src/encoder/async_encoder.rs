@@ -0,0 +1,206 @@
+use alloc::vec::Vec;
+use core::future::poll_fn;
+use core::pin::Pin;
+use core::task::{ready, Poll};
+
+use futures_io::AsyncWrite;
+
+use super::{Encoder, EncoderBuilder, EncodingError, ExtensionData, Repeat};
+use crate::common::{AnyExtension, Frame};
+
+impl EncoderBuilder {
+    /// Creates an [`AsyncEncoder`].
+    ///
+    /// The header is written along with the output of the first `async` call, or later if the
+    /// version is chosen automatically, see [`EncoderBuilder::set_version`].
+    pub fn build_async<W: AsyncWrite + Unpin>(
+        &self,
+        w: W,
+    ) -> Result<AsyncEncoder<W>, EncodingError> {
+        Ok(AsyncEncoder {
+            writer: w,
+            encoder: self.build(Vec::new())?,
+            written: 0,
+        })
+    }
+}
+
+/// GIF encoder writing to a [`futures_io::AsyncWrite`], without blocking.
+///
+/// The interface matches [`Encoder`], except that methods writing output are `async`. Each call
+/// encodes into an internal buffer first, which is then written out. Wrap tokio writers in
+/// `io::TokioCompat` when the `tokio` feature is enabled.
+///
+/// Call [`Self::finish`] to write the trailer, there is no `Drop` implementation that could do
+/// it. If a future is dropped before it completes, its remaining output is written by the next
+/// call.
+pub struct AsyncEncoder<W> {
+    writer: W,
+    encoder: Encoder<Vec<u8>>,
+    /// Bytes of the buffered output that have already been written.
+    written: usize,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncEncoder<W> {
+    /// Creates a new encoder.
+    ///
+    /// `global_palette` gives the global color palette in the format `[r, g, b, ...]`,
+    /// if no global palette shall be used an empty slice may be supplied.
+    ///
+    /// The header is written along with the output of the first `async` call.
+    pub fn new(
+        w: W,
+        width: u16,
+        height: u16,
+        global_palette: &[u8],
+    ) -> Result<Self, EncodingError> {
+        let encoder = Encoder::new(Vec::new(), width, height, global_palette)?;
+        Ok(Self {
+            writer: w,
+            encoder,
+            written: 0,
+        })
+    }
+
+    /// Writes the buffered output.
+    async fn write_buffered(&mut self) -> Result<(), EncodingError> {
+        let buffer = self.encoder.get_mut();
+        write_all(&mut self.writer, buffer, &mut self.written).await?;
+        buffer.clear();
+        self.written = 0;
+        Ok(())
+    }
+
+    /// Write an extension block that signals a repeat behaviour.
+    pub async fn set_repeat(&mut self, repeat: Repeat) -> Result<(), EncodingError> {
+        self.encoder.set_repeat(repeat)?;
+        self.write_buffered().await
+    }
+
+    /// Write an extension block that suggests a buffer size in bytes for reading the data stream.
+    ///
+    /// See [`Encoder::set_buffer_size_hint`].
+    pub async fn set_buffer_size_hint(&mut self, size: u32) -> Result<(), EncodingError> {
+        self.encoder.set_buffer_size_hint(size)?;
+        self.write_buffered().await
+    }
+
+    /// Writes a frame to the image.
+    ///
+    /// Rows of interlaced frames are reordered, see [`Encoder::write_frame`].
+    ///
+    /// Note: This function also writes a control extension if necessary.
+    pub async fn write_frame(&mut self, frame: &Frame<'_>) -> Result<(), EncodingError> {
+        self.encoder.write_frame(frame)?;
+        self.write_buffered().await
+    }
+
+    /// Writes a frame whose buffer is already in the order of the interlacing passes, see
+    /// [`Encoder::write_pre_interlaced_frame`].
+    ///
+    /// Note: This function also writes a control extension if necessary.
+    pub async fn write_pre_interlaced_frame(
+        &mut self,
+        frame: &Frame<'_>,
+    ) -> Result<(), EncodingError> {
+        self.encoder.write_pre_interlaced_frame(frame)?;
+        self.write_buffered().await
+    }
+
+    /// Writes a frame to the image, but expects `Frame.buffer` to contain LZW-encoded data
+    /// from [`Frame::make_lzw_pre_encoded`].
+    ///
+    /// Note: This function also writes a control extension if necessary.
+    pub async fn write_lzw_pre_encoded_frame(
+        &mut self,
+        frame: &Frame<'_>,
+    ) -> Result<(), EncodingError> {
+        self.encoder.write_lzw_pre_encoded_frame(frame)?;
+        self.write_buffered().await
+    }
+
+    /// Writes an extension to the image.
+    ///
+    /// It is normally not necessary to call this method manually.
+    pub async fn write_extension(&mut self, extension: ExtensionData) -> Result<(), EncodingError> {
+        self.encoder.write_extension(extension)?;
+        self.write_buffered().await
+    }
+
+    /// Writes a comment extension to the image, see [`Encoder::write_comment`].
+    pub async fn write_comment(&mut self, text: &[u8]) -> Result<(), EncodingError> {
+        self.encoder.write_comment(text)?;
+        self.write_buffered().await
+    }
+
+    /// Writes XMP metadata to the image, see [`Encoder::write_xmp_metadata`].
+    pub async fn write_xmp_metadata(&mut self, xmp: &[u8]) -> Result<(), EncodingError> {
+        self.encoder.write_xmp_metadata(xmp)?;
+        self.write_buffered().await
+    }
+
+    /// Writes an ICC color profile to the image.
+    ///
+    /// The profile should be written before the first frame.
+    pub async fn write_icc_profile(&mut self, icc: &[u8]) -> Result<(), EncodingError> {
+        self.encoder.write_icc_profile(icc)?;
+        self.write_buffered().await
+    }
+
+    /// Writes a raw extension to the image, see [`Encoder::write_raw_extension`].
+    pub async fn write_raw_extension(
+        &mut self,
+        func: AnyExtension,
+        data: &[&[u8]],
+    ) -> Result<(), EncodingError> {
+        self.encoder.write_raw_extension(func, data)?;
+        self.write_buffered().await
+    }
+
+    /// Gets a reference to the writer instance used by this encoder.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Gets a mutable reference to the writer instance used by this encoder.
+    ///
+    /// It is inadvisable to directly write to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Writes the trailer, flushes and returns the writer.
+    pub async fn finish(self) -> Result<W, EncodingError> {
+        let Self {
+            mut writer,
+            encoder,
+            mut written,
+        } = self;
+        let buffer = encoder.into_inner()?;
+        write_all(&mut writer, &buffer, &mut written).await?;
+        poll_fn(|cx| Pin::new(&mut writer).poll_flush(cx)).await?;
+        Ok(writer)
+    }
+}
+
+/// Writes `buffer` from `written` on, counting the written bytes.
+async fn write_all<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    buffer: &[u8],
+    written: &mut usize,
+) -> Result<(), EncodingError> {
+    poll_fn(|cx| {
+        while let Some(rest) = buffer.get(*written..).filter(|rest| !rest.is_empty()) {
+            match ready!(Pin::new(&mut *writer).poll_write(cx, rest))? {
+                0 => {
+                    return Poll::Ready(Err(
+                        std::io::Error::from(std::io::ErrorKind::WriteZero).into()
+                    ))
+                }
+                n => *written += n,
+            }
+        }
+        Poll::Ready(Ok(()))
+    })
+    .await
+}
@@ -442,3 +442,92 @@ impl<R: Read> BufRead for BufReader<R> {
         self.pos = core::cmp::min(self.pos + amt, self.cap);
     }
 }
+
+// ============================================================================
+// tokio: adapter to the futures I/O traits
+// ============================================================================
+
+/// Adapts a tokio reader or writer to the `futures` I/O traits used by
+/// [`AsyncDecoder`](crate::AsyncDecoder) and [`AsyncEncoder`](crate::AsyncEncoder).
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct TokioCompat<T>(T);
+
+#[cfg(feature = "tokio")]
+impl<T> TokioCompat<T> {
+    /// Wraps a tokio reader or writer.
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self(inner)
+    }
+
+    /// Gets a reference to the underlying reader or writer.
+    #[inline]
+    pub fn get_ref(&self) -> &T {
+        &self.0
+    }
+
+    /// Gets a mutable reference to the underlying reader or writer.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+
+    /// Unwraps this `TokioCompat`, returning the underlying reader or writer.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: tokio::io::AsyncRead + Unpin> futures_io::AsyncRead for TokioCompat<T> {
+    fn poll_read(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> core::task::Poll<std::io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        core::task::ready!(core::pin::Pin::new(&mut self.0).poll_read(cx, &mut buf))?;
+        core::task::Poll::Ready(Ok(buf.filled().len()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: tokio::io::AsyncBufRead + Unpin> futures_io::AsyncBufRead for TokioCompat<T> {
+    fn poll_fill_buf(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<&[u8]>> {
+        core::pin::Pin::new(&mut self.get_mut().0).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: core::pin::Pin<&mut Self>, amt: usize) {
+        core::pin::Pin::new(&mut self.0).consume(amt);
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T: tokio::io::AsyncWrite + Unpin> futures_io::AsyncWrite for TokioCompat<T> {
+    fn poll_write(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        buf: &[u8],
+    ) -> core::task::Poll<std::io::Result<usize>> {
+        core::pin::Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        core::pin::Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_close(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+    ) -> core::task::Poll<std::io::Result<()>> {
+        core::pin::Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
@@ -121,16 +121,31 @@ mod common;
 mod encoder;
 /// I/O traits and types for no_std support.
 pub mod io;
+pub mod optimize;
+pub mod quantize;
 mod reader;
 mod traits;
 
-pub use crate::common::{AnyExtension, DisposalMethod, Extension, Frame};
+pub use crate::common::{
+    AnyExtension, DisposalMethod, Extension, Frame, PixelAspectRatio, PlainText, RgbaFormat,
+};
 
+#[cfg(feature = "async")]
+pub use crate::reader::AsyncDecoder;
+#[cfg(feature = "std")]
+pub use crate::reader::IncrementalDecoder;
+pub use crate::reader::{ApplicationExtension, ApplicationExtensionHandler};
 pub use crate::reader::{ColorOutput, MemoryLimit};
-pub use crate::reader::{DecodeOptions, Decoder, Version};
+pub use crate::reader::{Comment, DecodeOptions, DecodedRow, Decoder, Screen, Version};
 pub use crate::reader::{DecodingError, DecodingFormatError};
+#[cfg(feature = "std")]
+pub use crate::reader::{FrameIndex, IndexedFrame};
 
-pub use crate::encoder::{Encoder, EncodingError, EncodingFormatError, ExtensionData, Repeat};
+#[cfg(feature = "async")]
+pub use crate::encoder::AsyncEncoder;
+pub use crate::encoder::{
+    Encoder, EncoderBuilder, EncodingError, EncodingFormatError, ExtensionData, FrameWriter, Repeat,
+};
 
 /// Low-level, advanced decoder. Prefer [`Decoder`] instead, which can stream frames too.
 pub mod streaming_decoder {
@@ -0,0 +1,414 @@
+//! Lossless size optimizations of animations.
+
+use alloc::borrow::Cow;
+use alloc::collections::btree_map::{BTreeMap, Entry};
+use alloc::vec::Vec;
+
+use crate::common::{DisposalMethod, Frame};
+use crate::encoder::{EncodingError, EncodingFormatError};
+
+/// Color of a pixel as shown on the canvas, all transparent pixels being equal.
+type Color = [u8; 4];
+
+const TRANSPARENT: Color = [0; 4];
+
+#[derive(Clone, Copy, Default)]
+struct Rect {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Optimizes an animation of full-canvas frames for size.
+///
+/// Every frame in `frames` must cover the whole `width` × `height` canvas and holds the complete
+/// image to be shown, transparent pixels showing the background. The frames are rewritten in
+/// place such that compositing them gives exactly the same images, similar to
+/// `gifsicle -O2`:
+///
+/// - each frame after the first is cropped to the area that changed,
+/// - the disposal method of each frame is chosen to minimize the area of the next frame,
+/// - unchanged pixels within the cropped area are replaced by a transparent index, which
+///   compresses better. A transparent index is only introduced if the palette has an unused entry
+///   or a local palette has room for one.
+///
+/// Transparent pixels of the optimized frames only show the background where the original
+/// frames are transparent, so the result is the same whether a decoder draws the background color
+/// of the file or a transparent canvas, like most browsers.
+///
+/// The number of frames and their delays are not changed. `global_palette` must be the palette
+/// that is passed to the [`Encoder`](crate::Encoder).
+pub fn optimize_frames(
+    width: u16,
+    height: u16,
+    global_palette: Option<&[u8]>,
+    frames: &mut [Frame<'_>],
+) -> Result<(), EncodingError> {
+    let canvas_width = usize::from(width);
+    let canvas_len = canvas_width * usize::from(height);
+    for frame in frames.iter() {
+        if frame.left != 0
+            || frame.top != 0
+            || frame.width != width
+            || frame.height != height
+            || frame.buffer.len() != canvas_len
+        {
+            return Err(EncodingFormatError::InvalidFrameSize.into());
+        }
+        if frame.palette.is_none() && global_palette.is_none() {
+            return Err(EncodingFormatError::MissingColorPalette.into());
+        }
+    }
+
+    let full = Rect {
+        left: 0,
+        top: 0,
+        width: canvas_width,
+        height: usize::from(height),
+    };
+    let Some((first, rest)) = frames.split_first_mut() else {
+        return Ok(());
+    };
+    // The canvas before the previous frame was drawn, and after it was drawn
+    let mut before_previous = vec![TRANSPARENT; canvas_len];
+    let mut previous_target = canvas_colors(first, global_palette);
+    let mut previous_indices = first.buffer.to_vec();
+    let mut previous_rect = full;
+    let mut previous = first;
+
+    for frame in rest {
+        let target = canvas_colors(frame, global_palette);
+
+        let mut cleared = previous_target.clone();
+        clear(&mut cleared, previous_rect, canvas_width);
+        let candidates = [
+            (DisposalMethod::Keep, &previous_target),
+            (DisposalMethod::Background, &cleared),
+            (DisposalMethod::Previous, &before_previous),
+        ];
+        let best = candidates
+            .into_iter()
+            .filter_map(|(dispose, base)| {
+                let rect = changed_rect(base, &target, canvas_width)?;
+                Some((dispose, base, rect))
+            })
+            .min_by_key(|(_, _, rect)| rect.map_or(0, |r| r.width * r.height));
+        let (base, rect) = match best {
+            Some((dispose, base, rect)) => {
+                previous.dispose = dispose;
+                (base.clone(), rect)
+            }
+            None => {
+                // Pixels outside of the previous frame have to become transparent, so it is
+                // extended to the whole canvas and cleared.
+                let transparent = previous.transparent;
+                crop(
+                    previous,
+                    &previous_indices,
+                    &before_previous,
+                    &previous_target,
+                    full,
+                    transparent,
+                    canvas_width,
+                );
+                previous.dispose = DisposalMethod::Background;
+                let base = vec![TRANSPARENT; canvas_len];
+                let rect = changed_rect(&base, &target, canvas_width).unwrap_or_default();
+                (base, rect)
+            }
+        };
+
+        // A frame must contain at least one pixel
+        let rect = rect.unwrap_or(Rect {
+            left: 0,
+            top: 0,
+            width: 1,
+            height: 1,
+        });
+        let transparent = transparent_index(frame, global_palette);
+        let indices = frame.buffer.to_vec();
+        crop(
+            frame,
+            &indices,
+            &base,
+            &target,
+            rect,
+            transparent,
+            canvas_width,
+        );
+
+        before_previous = base;
+        previous_target = target;
+        previous_indices = indices;
+        previous_rect = rect;
+        previous = frame;
+    }
+    previous.dispose = DisposalMethod::Keep;
+    Ok(())
+}
+
+/// Replaces the frame by the given area of a full-canvas frame.
+///
+/// Pixels that do not change the canvas are made transparent, if a transparent index is given.
+fn crop(
+    frame: &mut Frame<'_>,
+    indices: &[u8],
+    base: &[Color],
+    target: &[Color],
+    rect: Rect,
+    transparent: Option<u8>,
+    canvas_width: usize,
+) {
+    let mut buffer = Vec::with_capacity(rect.width * rect.height);
+    for y in rect.top..rect.top + rect.height {
+        for x in rect.left..rect.left + rect.width {
+            let i = y * canvas_width + x;
+            match transparent {
+                Some(transparent) if base[i] == target[i] => buffer.push(transparent),
+                _ => buffer.push(indices[i]),
+            }
+        }
+    }
+    if transparent.is_some() {
+        frame.transparent = transparent;
+    }
+    frame.buffer = Cow::Owned(buffer);
+    frame.left = rect.left as u16;
+    frame.top = rect.top as u16;
+    frame.width = rect.width as u16;
+    frame.height = rect.height as u16;
+}
+
+fn clear(canvas: &mut [Color], rect: Rect, canvas_width: usize) {
+    for y in rect.top..rect.top + rect.height {
+        let row = y * canvas_width + rect.left;
+        canvas[row..row + rect.width].fill(TRANSPARENT);
+    }
+}
+
+/// Colors of a full-canvas frame.
+fn canvas_colors(frame: &Frame<'_>, global_palette: Option<&[u8]>) -> Vec<Color> {
+    let palette = frame.palette.as_deref().or(global_palette).unwrap_or(&[]);
+    frame
+        .buffer
+        .iter()
+        .map(|&index| {
+            if Some(index) == frame.transparent {
+                return TRANSPARENT;
+            }
+            // Indices beyond the palette refer to the zero padding written by the encoder
+            match palette.get(usize::from(index) * 3..usize::from(index) * 3 + 3) {
+                Some(&[r, g, b]) => [r, g, b, 0xFF],
+                _ => [0, 0, 0, 0xFF],
+            }
+        })
+        .collect()
+}
+
+/// Bounding rectangle of the pixels that differ, `None` within if nothing changed.
+///
+/// Returns `None` if the target can not be drawn onto `base`, because a pixel would have to
+/// become transparent.
+fn changed_rect(base: &[Color], target: &[Color], width: usize) -> Option<Option<Rect>> {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+    for (i, (base, target)) in base.iter().zip(target).enumerate() {
+        if base == target {
+            continue;
+        }
+        if *target == TRANSPARENT {
+            return None;
+        }
+        let (x, y) = (i % width, i / width);
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    if min_x == usize::MAX {
+        return Some(None);
+    }
+    Some(Some(Rect {
+        left: min_x,
+        top: min_y,
+        width: max_x - min_x + 1,
+        height: max_y - min_y + 1,
+    }))
+}
+
+/// Finds an index that can be used for transparency, adding one to a local palette if needed.
+fn transparent_index(frame: &mut Frame<'_>, global_palette: Option<&[u8]>) -> Option<u8> {
+    if frame.transparent.is_some() {
+        return frame.transparent;
+    }
+    let mut used = [false; 256];
+    for &index in frame.buffer.iter() {
+        used[usize::from(index)] = true;
+    }
+    let unused = used.iter().position(|&used| !used)?;
+    match &mut frame.palette {
+        Some(palette) if unused >= palette.len() / 3 => {
+            palette.resize(unused * 3 + 3, 0);
+        }
+        Some(_) => {}
+        // The global palette is shared, only use entries that are written anyway
+        None if unused >= global_palette.map_or(0, |p| p.len() / 3) => return None,
+        None => {}
+    }
+    Some(unused as u8)
+}
+
+/// Colors used by a group of frames sharing a palette, ordered by frame and then by index.
+struct SharedPalette {
+    colors: Vec<[u8; 3]>,
+    lookup: BTreeMap<[u8; 3], u8>,
+    transparent: Option<u8>,
+}
+
+impl SharedPalette {
+    /// Collects the colors used by `frames`, `None` if they do not fit into one palette.
+    fn new<'a, 'b: 'a>(
+        frames: impl IntoIterator<Item = &'a Frame<'b>>,
+        global_palette: &[u8],
+    ) -> Option<Self> {
+        let mut colors = Vec::new();
+        let mut lookup = BTreeMap::new();
+        let mut needs_transparent = false;
+        for frame in frames {
+            let palette = frame.palette.as_deref().unwrap_or(global_palette);
+            let mut used = [false; 256];
+            for &index in frame.buffer.iter() {
+                used[usize::from(index)] = true;
+            }
+            if let Some(transparent) = frame.transparent {
+                needs_transparent = true;
+                used[usize::from(transparent)] = false;
+            }
+            for index in (0..256).filter(|&i| used[i]) {
+                let color = palette_color(palette, index);
+                if let Entry::Vacant(entry) = lookup.entry(color) {
+                    entry.insert(u8::try_from(colors.len()).ok()?);
+                    colors.push(color);
+                }
+            }
+        }
+        let transparent = if needs_transparent {
+            let index = u8::try_from(colors.len()).ok()?;
+            colors.push([0; 3]);
+            Some(index)
+        } else {
+            None
+        };
+        if colors.is_empty() {
+            colors.push([0; 3]);
+        }
+        Some(Self {
+            colors,
+            lookup,
+            transparent,
+        })
+    }
+
+    fn palette(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+
+    /// Number of entries in the color table written by the encoder.
+    fn table_len(&self) -> usize {
+        self.colors.len().max(2).next_power_of_two()
+    }
+
+    /// Changes the indices of a frame to refer to this palette.
+    fn remap(&self, frame: &mut Frame<'_>, palette: &[u8]) {
+        let mut table = [0; 256];
+        for (index, entry) in table.iter_mut().enumerate() {
+            let color = palette_color(palette, index);
+            *entry = self.lookup.get(&color).copied().unwrap_or(0);
+        }
+        if let (Some(old), Some(new)) = (frame.transparent, self.transparent) {
+            table[usize::from(old)] = new;
+            frame.transparent = Some(new);
+        }
+        frame
+            .buffer
+            .to_mut()
+            .iter_mut()
+            .for_each(|i| *i = table[usize::from(*i)]);
+    }
+}
+
+/// Color of a palette entry, entries beyond the palette being black like the encoder's padding.
+fn palette_color(palette: &[u8], index: usize) -> [u8; 3] {
+    match palette.get(index * 3..index * 3 + 3) {
+        Some(&[r, g, b]) => [r, g, b],
+        _ => [0; 3],
+    }
+}
+
+pub(crate) fn optimize_local_palette(frame: &mut Frame<'_>) {
+    let Some(palette) = frame.palette.take() else {
+        return;
+    };
+    // A single frame always fits, as it can not use more than 256 colors
+    if let Some(shared) = SharedPalette::new([&*frame], &palette) {
+        shared.remap(frame, &palette);
+        frame.palette = Some(shared.palette());
+    } else {
+        frame.palette = Some(palette);
+    }
+}
+
+/// Shrinks the palettes of an animation.
+///
+/// Unused entries are removed and duplicate colors are merged, in the global palette as well as
+/// in local palettes, see [`Frame::optimize_palette`]. The frames are remapped accordingly, which
+/// also reduces the LZW code size.
+///
+/// If the colors of all frames fit into a single palette, without it needing more bits per pixel
+/// than the largest local palette, the local palettes are replaced by a shared global palette.
+/// An empty `global_palette` is treated as no global palette, and becomes empty if no frame uses
+/// it anymore.
+pub fn optimize_palettes(
+    global_palette: &mut Vec<u8>,
+    frames: &mut [Frame<'_>],
+) -> Result<(), EncodingError> {
+    if frames
+        .iter()
+        .any(|frame| frame.palette.is_none() && global_palette.is_empty())
+    {
+        return Err(EncodingFormatError::MissingColorPalette.into());
+    }
+
+    if let Some(shared) = SharedPalette::new(frames.iter(), global_palette) {
+        let largest_local = frames
+            .iter()
+            .filter(|frame| frame.palette.is_some())
+            .filter_map(|frame| SharedPalette::new([frame], &[]))
+            .map(|local| local.table_len())
+            .max();
+        if largest_local.map_or(true, |largest| shared.table_len() <= largest) {
+            for frame in frames.iter_mut() {
+                let palette = frame.palette.take();
+                shared.remap(frame, palette.as_deref().unwrap_or(global_palette));
+            }
+            *global_palette = shared.palette();
+            return Ok(());
+        }
+    }
+
+    for frame in frames.iter_mut() {
+        optimize_local_palette(frame);
+    }
+    if frames.iter().all(|frame| frame.palette.is_some()) {
+        global_palette.clear();
+    } else if let Some(shared) = SharedPalette::new(
+        frames.iter().filter(|frame| frame.palette.is_none()),
+        global_palette,
+    ) {
+        for frame in frames.iter_mut().filter(|frame| frame.palette.is_none()) {
+            shared.remap(frame, global_palette);
+        }
+        *global_palette = shared.palette();
+    }
+    Ok(())
+}
@@ -0,0 +1,1012 @@
+//! Color quantization, reducing true color images to a palette.
+//!
+//! A [`Quantizer`] is used by [`Frame::from_rgba_with_quantizer`] when an image has more than 256
+//! colors. [`MedianCut`] and [`Octree`] are always available, [`NeuQuant`] requires the
+//! `color_quant` feature.
+
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use crate::common::{check_pixels_len, unwrap_frame};
+use crate::{EncodingError, EncodingFormatError, Frame};
+
+/// Computes a palette that represents the colors of an image.
+pub trait Quantizer {
+    /// Returns a palette of at most `max_colors` RGB triples for the RGBA `pixels`.
+    ///
+    /// Pixels with an alpha of zero are transparent and should not influence the palette, all
+    /// other pixels are opaque. `max_colors` is in the range 1 to 256.
+    fn palette(&self, pixels: &[u8], max_colors: usize) -> Vec<u8>;
+}
+
+impl<Q: Quantizer + ?Sized> Quantizer for &Q {
+    fn palette(&self, pixels: &[u8], max_colors: usize) -> Vec<u8> {
+        (**self).palette(pixels, max_colors)
+    }
+}
+
+/// Number of occurrences of each opaque color, sorted by color.
+fn histogram(pixels: &[u8]) -> Vec<([u8; 3], u32)> {
+    let mut colors: Vec<u32> = pixels
+        .chunks_exact(4)
+        .filter(|pixel| pixel[3] != 0)
+        .map(|pixel| u32::from_be_bytes([0, pixel[0], pixel[1], pixel[2]]))
+        .collect();
+    colors.sort_unstable();
+    let mut histogram: Vec<([u8; 3], u32)> = Vec::new();
+    for color in colors {
+        let [_, r, g, b] = color.to_be_bytes();
+        match histogram.last_mut() {
+            Some((last, count)) if *last == [r, g, b] => *count += 1,
+            _ => histogram.push(([r, g, b], 1)),
+        }
+    }
+    histogram
+}
+
+/// Weighted average of colors.
+fn average(colors: &[([u8; 3], u32)]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    let mut total = 0u64;
+    for &(color, count) in colors {
+        for (sum, c) in sum.iter_mut().zip(color) {
+            *sum += u64::from(c) * u64::from(count);
+        }
+        total += u64::from(count);
+    }
+    sum.map(|sum| ((sum + total / 2) / total.max(1)) as u8)
+}
+
+/// Median cut quantization.
+///
+/// Recursively splits the set of colors at the median of its widest channel, and uses the average
+/// color of each part. Fast and deterministic, with good results for images with distinct colors.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MedianCut;
+
+impl Quantizer for MedianCut {
+    fn palette(&self, pixels: &[u8], max_colors: usize) -> Vec<u8> {
+        let mut colors = histogram(pixels);
+        // Boxes are ranges of `colors`, with the channel of the widest range and its width
+        let mut boxes = vec![(0..colors.len(), 0usize, 0u8)];
+        let measure = |colors: &[([u8; 3], u32)]| {
+            (0..3)
+                .map(|channel| {
+                    let (min, max) = colors.iter().fold((u8::MAX, 0), |(min, max), (c, _)| {
+                        (min.min(c[channel]), max.max(c[channel]))
+                    });
+                    (channel, max.saturating_sub(min))
+                })
+                .max_by_key(|&(_, width)| width)
+                .unwrap_or((0, 0))
+        };
+        let (channel, width) = measure(&colors);
+        boxes[0].1 = channel;
+        boxes[0].2 = width;
+
+        while boxes.len() < max_colors {
+            // Split the widest box that has more than one color
+            let Some(index) = (0..boxes.len())
+                .filter(|&i| boxes[i].0.len() > 1)
+                .max_by_key(|&i| (boxes[i].2, boxes[i].0.len()))
+            else {
+                break;
+            };
+            let (range, channel, _) = boxes.swap_remove(index);
+            let part = &mut colors[range.clone()];
+            part.sort_unstable_by_key(|(color, _)| color[channel]);
+            let total: u64 = part.iter().map(|&(_, count)| u64::from(count)).sum();
+            let mut below = 0;
+            let median = part
+                .iter()
+                .position(|&(_, count)| {
+                    below += u64::from(count);
+                    below * 2 >= total
+                })
+                .unwrap_or(0);
+            let split = range.start + (median + 1).min(range.len() - 1);
+            for range in [range.start..split, split..range.end] {
+                let (channel, width) = measure(&colors[range.clone()]);
+                boxes.push((range, channel, width));
+            }
+        }
+
+        boxes
+            .iter()
+            .filter(|(range, _, _)| !range.is_empty())
+            .flat_map(|(range, _, _)| average(&colors[range.clone()]))
+            .collect()
+    }
+}
+
+/// Octree quantization.
+///
+/// Inserts all colors into a tree that splits each channel by one bit per level, then merges the
+/// deepest leaves until few enough remain. Preserves rarely used but distinct colors well.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Octree;
+
+#[derive(Default)]
+struct OctreeNode {
+    /// Indices of the children, 0 for none as the root is never a child.
+    /// Leaves at depth 8 hold a single color.
+    children: [u32; 8],
+    sum: [u64; 3],
+    count: u64,
+    leaf: bool,
+}
+
+impl Quantizer for Octree {
+    fn palette(&self, pixels: &[u8], max_colors: usize) -> Vec<u8> {
+        let mut nodes = vec![OctreeNode::default()];
+        // Inner nodes of each depth, for merging the deepest ones first
+        let mut levels: [Vec<u32>; 8] = Default::default();
+        levels[0].push(0);
+        let mut leaves = 0;
+        for (color, count) in histogram(pixels) {
+            let mut node = 0;
+            for bit in (0..8).rev() {
+                let child = usize::from(
+                    (color[0] >> bit & 1) << 2 | (color[1] >> bit & 1) << 1 | (color[2] >> bit & 1),
+                );
+                if nodes[node].children[child] == 0 {
+                    let index = nodes.len() as u32;
+                    nodes[node].children[child] = index;
+                    nodes.push(OctreeNode::default());
+                    if bit == 0 {
+                        nodes[index as usize].leaf = true;
+                        leaves += 1;
+                    } else {
+                        levels[8 - bit].push(index);
+                    }
+                }
+                node = nodes[node].children[child] as usize;
+            }
+            let leaf = &mut nodes[node];
+            for (sum, c) in leaf.sum.iter_mut().zip(color) {
+                *sum += u64::from(c) * u64::from(count);
+            }
+            leaf.count += u64::from(count);
+        }
+
+        // Merge the children of the deepest, least used inner nodes
+        for inner in levels.iter_mut().rev() {
+            if leaves <= max_colors {
+                break;
+            }
+            inner.sort_unstable_by_key(|&node| core::cmp::Reverse(subtree_count(&nodes, node)));
+            while leaves > max_colors {
+                let Some(node) = inner.pop() else {
+                    break;
+                };
+                let children = core::mem::take(&mut nodes[node as usize].children);
+                let mut merged = 0;
+                for child in children.into_iter().filter(|&child| child != 0) {
+                    let child = &nodes[child as usize];
+                    let (sum, count) = (child.sum, child.count);
+                    let node = &mut nodes[node as usize];
+                    node.sum.iter_mut().zip(sum).for_each(|(a, b)| *a += b);
+                    node.count += count;
+                    merged += 1;
+                }
+                nodes[node as usize].leaf = true;
+                leaves = leaves + 1 - merged;
+            }
+        }
+
+        let mut palette = Vec::with_capacity(leaves * 3);
+        collect_leaves(&nodes, 0, &mut palette);
+        palette
+    }
+}
+
+/// Number of pixels in the leaves below a node that is not yet merged.
+fn subtree_count(nodes: &[OctreeNode], node: u32) -> u64 {
+    let node = &nodes[node as usize];
+    if node.leaf {
+        return node.count;
+    }
+    node.children
+        .iter()
+        .filter(|&&child| child != 0)
+        .map(|&child| subtree_count(nodes, child))
+        .sum()
+}
+
+fn collect_leaves(nodes: &[OctreeNode], node: usize, palette: &mut Vec<u8>) {
+    let node = &nodes[node];
+    if node.leaf {
+        palette.extend(
+            node.sum
+                .map(|sum| ((sum + node.count / 2) / node.count.max(1)) as u8),
+        );
+        return;
+    }
+    for &child in node.children.iter().filter(|&&child| child != 0) {
+        collect_leaves(nodes, child as usize, palette);
+    }
+}
+
+/// NeuQuant neural network quantization, from the `color_quant` crate.
+///
+/// Gives high quality results for photographic images.
+#[cfg(feature = "color_quant")]
+#[derive(Clone, Copy, Debug)]
+pub struct NeuQuant {
+    speed: i32,
+}
+
+#[cfg(feature = "color_quant")]
+impl NeuQuant {
+    /// Creates the quantizer with a `speed` in the range [1, 30].
+    ///
+    /// The higher the value the faster it runs at the cost of image quality.
+    /// A `speed` of 10 is a good compromise between speed and quality.
+    ///
+    /// # Panics:
+    /// *   If `speed < 1` or `speed > 30`
+    #[track_caller]
+    #[must_use]
+    pub fn new(speed: i32) -> Self {
+        assert!(
+            speed >= 1 && speed <= 30,
+            "speed needs to be in the range [1, 30]"
+        );
+        Self { speed }
+    }
+}
+
+#[cfg(feature = "color_quant")]
+impl Quantizer for NeuQuant {
+    fn palette(&self, pixels: &[u8], max_colors: usize) -> Vec<u8> {
+        let opaque: Vec<u8> = pixels
+            .chunks_exact(4)
+            .filter(|pixel| pixel[3] != 0)
+            .flatten()
+            .copied()
+            .collect();
+        if opaque.is_empty() {
+            return Vec::new();
+        }
+        // The network needs at least a few neurons
+        let nq = color_quant::NeuQuant::new(self.speed, max_colors.max(4), &opaque);
+        let mut palette = nq.color_map_rgb();
+        palette.truncate(max_colors * 3);
+        palette
+    }
+}
+
+/// Method for distributing the quantization error when mapping pixels to a palette.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Every pixel is mapped to the nearest color.
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion, to the right and the next row.
+    FloydSteinberg,
+    /// Atkinson error diffusion, which only diffuses 3/4 of the error for higher contrast.
+    Atkinson,
+    /// Sierra error diffusion, to the right and the next two rows.
+    Sierra,
+    /// Ordered dithering with an 8×8 Bayer matrix.
+    ///
+    /// The pattern is anchored to the pixel position, so unchanged regions stay identical between
+    /// frames of an animation.
+    Bayer,
+}
+
+impl Dither {
+    /// Offsets and weights of the neighbours the error is diffused to, and the divisor.
+    fn kernel(self) -> (&'static [(isize, usize, i32)], i32) {
+        match self {
+            Dither::FloydSteinberg => (&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16),
+            Dither::Atkinson => (
+                &[
+                    (1, 0, 1),
+                    (2, 0, 1),
+                    (-1, 1, 1),
+                    (0, 1, 1),
+                    (1, 1, 1),
+                    (0, 2, 1),
+                ],
+                8,
+            ),
+            Dither::Sierra => (
+                &[
+                    (1, 0, 5),
+                    (2, 0, 3),
+                    (-2, 1, 2),
+                    (-1, 1, 4),
+                    (0, 1, 5),
+                    (1, 1, 4),
+                    (2, 1, 2),
+                    (-1, 2, 2),
+                    (0, 2, 3),
+                    (1, 2, 2),
+                ],
+                32,
+            ),
+            Dither::None | Dither::Bayer => (&[], 1),
+        }
+    }
+}
+
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Options for converting true color pixels to a paletted [`Frame`].
+///
+/// The same options can be used for all frames of an animation. Conversion is deterministic, and
+/// [`map_rgba_following`](Self::map_rgba_following) keeps pixels that did not change since the
+/// previous frame identical, so that dithering does not shimmer.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameOptions {
+    dither: Dither,
+    strength: f32,
+    serpentine: bool,
+    alpha_threshold: u8,
+    alpha_dither: bool,
+    matte: Option<[u8; 3]>,
+}
+
+impl FrameOptions {
+    /// Creates options without dithering.
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            dither: Dither::None,
+            strength: 1.0,
+            serpentine: true,
+            alpha_threshold: 1,
+            alpha_dither: false,
+            matte: None,
+        }
+    }
+
+    /// Configure the dithering method.
+    #[inline]
+    pub fn set_dither(&mut self, dither: Dither) {
+        self.dither = dither;
+    }
+
+    /// Configure how much of the error is diffused, or the amplitude of ordered dithering.
+    ///
+    /// The value is clamped to the range [0, 1], defaults to 1.
+    #[inline]
+    pub fn set_dither_strength(&mut self, strength: f32) {
+        self.strength = strength.clamp(0.0, 1.0);
+    }
+
+    /// Configure whether error diffusion alternates the direction of every row.
+    ///
+    /// Serpentine scanning avoids diagonal artifacts, enabled by default.
+    #[inline]
+    pub fn set_serpentine(&mut self, serpentine: bool) {
+        self.serpentine = serpentine;
+    }
+
+    /// Configure the smallest alpha value of an opaque pixel.
+    ///
+    /// Pixels with a lower alpha are transparent, pixels with an alpha of zero always are.
+    /// Defaults to 1, so that any non-zero alpha is opaque.
+    #[inline]
+    pub fn set_alpha_threshold(&mut self, threshold: u8) {
+        self.alpha_threshold = threshold;
+    }
+
+    /// Configure ordered dithering of the alpha channel.
+    ///
+    /// Partially transparent pixels become opaque in a Bayer pattern with a density proportional
+    /// to their alpha, instead of comparing to the alpha threshold. Disabled by default.
+    #[inline]
+    pub fn set_alpha_dither(&mut self, alpha_dither: bool) {
+        self.alpha_dither = alpha_dither;
+    }
+
+    /// Configure the background color partially transparent pixels are composited over.
+    ///
+    /// Opaque pixels with an alpha below 0xFF are blended with the matte color by their alpha
+    /// before quantization, which avoids fringes around anti-aliased edges when the image is
+    /// shown on a similar background. Without a matte their color is used as is, the default.
+    #[inline]
+    pub fn set_matte(&mut self, matte: Option<[u8; 3]>) {
+        self.matte = matte;
+    }
+
+    /// Creates a frame from pixels in RGBA format, reducing the colors with `quantizer`.
+    ///
+    /// Like [`Frame::from_rgba_with_quantizer`], but the pixels are dithered when the image has
+    /// more than 256 colors. The alpha options are applied in place, leaving alpha at 0 or 0xFF.
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    #[track_caller]
+    pub fn quantize_rgba<Q: Quantizer + ?Sized>(
+        &self,
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+        quantizer: &Q,
+    ) -> Frame<'static> {
+        unwrap_frame(self.try_quantize_rgba(width, height, pixels, quantizer))
+    }
+
+    /// Creates a frame from pixels in RGBA format, reducing the colors with `quantizer`.
+    ///
+    /// See [`quantize_rgba`](Self::quantize_rgba).
+    ///
+    /// # Errors:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    pub fn try_quantize_rgba<Q: Quantizer + ?Sized>(
+        &self,
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+        quantizer: &Q,
+    ) -> Result<Frame<'static>, EncodingError> {
+        check_pixels_len(width, height, 4, pixels)?;
+        let (palette, buffer, transparent) = self.quantize(usize::from(width), pixels, quantizer);
+        Ok(Frame {
+            width,
+            height,
+            buffer: Cow::Owned(buffer),
+            palette: Some(palette),
+            transparent,
+            ..Frame::default()
+        })
+    }
+
+    /// Creates a frame from pixels in RGBA format, mapped to the colors of `palette`.
+    ///
+    /// Pixels that are transparent by the alpha options use the `transparent` index, which is never
+    /// used for opaque pixels. Without a transparent index alpha only affects the matte. The frame has no local palette, so
+    /// `palette` is expected to be the global palette of the image.
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    /// *   If the palette is empty or has more than 256 colors.
+    #[track_caller]
+    #[must_use]
+    pub fn map_rgba(
+        &self,
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        palette: &[u8],
+        transparent: Option<u8>,
+    ) -> Frame<'static> {
+        unwrap_frame(self.try_map_rgba(width, height, pixels, palette, transparent))
+    }
+
+    /// Creates a frame from pixels in RGBA format, mapped to the colors of `palette`.
+    ///
+    /// See [`map_rgba`](Self::map_rgba).
+    ///
+    /// # Errors:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    /// *   If the palette is empty or has more than 256 colors.
+    pub fn try_map_rgba(
+        &self,
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        palette: &[u8],
+        transparent: Option<u8>,
+    ) -> Result<Frame<'static>, EncodingError> {
+        check_pixels_len(width, height, 4, pixels)?;
+        check_palette(palette)?;
+        Ok(Frame {
+            width,
+            height,
+            buffer: Cow::Owned(self.map(usize::from(width), pixels, palette, transparent, None)),
+            transparent,
+            ..Frame::default()
+        })
+    }
+
+    /// Like [`map_rgba`](Self::map_rgba) for the next frame of an animation.
+    ///
+    /// `previous` is the result of mapping `previous_pixels`. Pixels that are equal in both
+    /// frames keep their index, and do not take up the error diffused from changed pixels. Size,
+    /// position and transparent index are taken from `previous`.
+    ///
+    /// # Panics:
+    /// *   If the length of any pixels does not equal `width * height * 4` of `previous`.
+    /// *   If the palette is empty or has more than 256 colors.
+    #[track_caller]
+    #[must_use]
+    pub fn map_rgba_following(
+        &self,
+        previous: &Frame<'_>,
+        previous_pixels: &[u8],
+        pixels: &[u8],
+        palette: &[u8],
+    ) -> Frame<'static> {
+        unwrap_frame(self.try_map_rgba_following(previous, previous_pixels, pixels, palette))
+    }
+
+    /// Like [`try_map_rgba`](Self::try_map_rgba) for the next frame of an animation.
+    ///
+    /// See [`map_rgba_following`](Self::map_rgba_following).
+    ///
+    /// # Errors:
+    /// *   If the length of any pixels does not equal `width * height * 4` of `previous`, or the
+    ///     buffer of `previous` does not match its size.
+    /// *   If the palette is empty or has more than 256 colors.
+    pub fn try_map_rgba_following(
+        &self,
+        previous: &Frame<'_>,
+        previous_pixels: &[u8],
+        pixels: &[u8],
+        palette: &[u8],
+    ) -> Result<Frame<'static>, EncodingError> {
+        let (width, height) = (previous.width, previous.height);
+        check_pixels_len(width, height, 4, pixels)?;
+        check_pixels_len(width, height, 4, previous_pixels)?;
+        check_pixels_len(width, height, 1, &previous.buffer)?;
+        check_palette(palette)?;
+        let previous_frame = (previous_pixels, &*previous.buffer);
+        Ok(Frame {
+            left: previous.left,
+            top: previous.top,
+            width,
+            height,
+            buffer: Cow::Owned(self.map(
+                usize::from(width),
+                pixels,
+                palette,
+                previous.transparent,
+                Some(previous_frame),
+            )),
+            transparent: previous.transparent,
+            ..Frame::default()
+        })
+    }
+
+    /// Converts RGBA pixels to a palette, indices and the transparent index.
+    ///
+    /// The alpha options are applied in place. Images with at most 256 distinct colors get an
+    /// exact palette, others are reduced by the `quantizer` and dithered.
+    pub(crate) fn quantize<Q: Quantizer + ?Sized>(
+        &self,
+        width: usize,
+        pixels: &mut [u8],
+        quantizer: &Q,
+    ) -> (Vec<u8>, Vec<u8>, Option<u8>) {
+        self.apply_alpha(width, pixels);
+        let mut transparent: Option<[u8; 4]> = None;
+        for pix in pixels.chunks_exact_mut(4) {
+            if pix[3] != 0 {
+                continue;
+            }
+
+            if let Some([r, g, b, a]) = transparent {
+                pix[0] = r;
+                pix[1] = g;
+                pix[2] = b;
+                pix[3] = a;
+            } else {
+                transparent = Some([pix[0], pix[1], pix[2], pix[3]]);
+            }
+        }
+
+        // Attempt to build a palette of all colors. If we go over 256 colors,
+        // switch to the quantizer.
+        let mut colors: BTreeSet<(u8, u8, u8, u8)> = BTreeSet::new();
+        for pixel in pixels.chunks_exact(4) {
+            if colors.insert((pixel[0], pixel[1], pixel[2], pixel[3])) && colors.len() > 256 {
+                return self.quantize_lossy(width, pixels, transparent, quantizer);
+            }
+        }
+
+        // Palette size <= 256 elements, we can build an exact palette.
+        let mut colors_vec: Vec<(u8, u8, u8, u8)> = colors.into_iter().collect();
+        colors_vec.sort_unstable();
+        let palette = colors_vec
+            .iter()
+            .flat_map(|&(r, g, b, _a)| [r, g, b])
+            .collect();
+        let colors_lookup: BTreeMap<(u8, u8, u8, u8), u8> =
+            colors_vec.into_iter().zip(0..=255).collect();
+
+        let index_of = |pixel: &[u8]| {
+            colors_lookup
+                .get(&(pixel[0], pixel[1], pixel[2], pixel[3]))
+                .copied()
+                .unwrap_or(0)
+        };
+
+        (
+            palette,
+            pixels.chunks_exact(4).map(index_of).collect(),
+            transparent.map(|t| index_of(&t)),
+        )
+    }
+
+    /// Reduces the opaque colors with the quantizer, transparent pixels get an entry of their own.
+    fn quantize_lossy<Q: Quantizer + ?Sized>(
+        &self,
+        width: usize,
+        pixels: &[u8],
+        transparent: Option<[u8; 4]>,
+        quantizer: &Q,
+    ) -> (Vec<u8>, Vec<u8>, Option<u8>) {
+        let max_colors = if transparent.is_some() { 255 } else { 256 };
+        let mut palette = quantizer.palette(pixels, max_colors);
+        palette.truncate((palette.len() / 3).min(max_colors) * 3);
+        if palette.is_empty() {
+            palette.extend_from_slice(&[0, 0, 0]);
+        }
+        let transparent = transparent.map(|[r, g, b, _]| {
+            palette.extend_from_slice(&[r, g, b]);
+            (palette.len() / 3 - 1) as u8
+        });
+        let buffer = self.map(width, pixels, &palette, transparent, None);
+        (palette, buffer, transparent)
+    }
+
+    /// Alpha below which a pixel at `n` is transparent.
+    fn alpha_cutoff(&self, width: usize, n: usize) -> u8 {
+        if self.alpha_dither {
+            let (x, y) = (n % width, n / width);
+            BAYER[y % 8][x % 8] * 4 + 2
+        } else {
+            self.alpha_threshold
+        }
+    }
+
+    /// Applies the alpha threshold and matte, alpha of the result is either 0 or 0xFF.
+    fn prepare_pixel(&self, [r, g, b, a]: [u8; 4], cutoff: u8) -> [u8; 4] {
+        if a == 0 || a < cutoff {
+            return [r, g, b, 0];
+        }
+        match self.matte {
+            Some(matte) if a != 0xFF => {
+                let blend = |c: u8, m: u8| {
+                    let (c, m, a) = (u32::from(c), u32::from(m), u32::from(a));
+                    ((c * a + m * (255 - a) + 127) / 255) as u8
+                };
+                [
+                    blend(r, matte[0]),
+                    blend(g, matte[1]),
+                    blend(b, matte[2]),
+                    0xFF,
+                ]
+            }
+            _ => [r, g, b, 0xFF],
+        }
+    }
+
+    /// Applies the alpha options to all pixels in place.
+    pub(crate) fn apply_alpha(&self, width: usize, pixels: &mut [u8]) {
+        for (n, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+            let prepared = self.prepare_pixel(
+                [pixel[0], pixel[1], pixel[2], pixel[3]],
+                self.alpha_cutoff(width, n),
+            );
+            pixel.copy_from_slice(&prepared);
+        }
+    }
+
+    /// Maps pixels to the palette with dithering, optionally keeping the indices of pixels that
+    /// are unchanged from the previous pixels and buffer.
+    fn map(
+        &self,
+        width: usize,
+        pixels: &[u8],
+        palette: &[u8],
+        transparent: Option<u8>,
+        previous: Option<(&[u8], &[u8])>,
+    ) -> Vec<u8> {
+        let mut nearest = NearestColor::new(palette, transparent);
+        let mut buffer = vec![0; pixels.len() / 4];
+        if width == 0 {
+            return buffer;
+        }
+        let strength = (self.strength * 256.0) as i32;
+        let (kernel, divisor) = self.dither.kernel();
+        // Accumulated error of the current and next two rows, padded by 2 on both sides
+        let stride = (width + 4) * 3;
+        let mut errors = vec![0i32; stride * 3];
+
+        for (y, row) in pixels.chunks_exact(width * 4).enumerate() {
+            let reverse = self.serpentine && y % 2 == 1;
+            for i in 0..width {
+                let x = if reverse { width - 1 - i } else { i };
+                let n = y * width + x;
+                let raw = &row[x * 4..][..4];
+                let pixel = self.prepare_pixel(
+                    [raw[0], raw[1], raw[2], raw[3]],
+                    self.alpha_cutoff(width, n),
+                );
+                buffer[n] = match (transparent, previous) {
+                    (Some(transparent), _) if pixel[3] == 0 => transparent,
+                    (_, Some((previous_pixels, previous_buffer)))
+                        if previous_pixels[n * 4..][..4] == *raw =>
+                    {
+                        previous_buffer[n]
+                    }
+                    _ => {
+                        let error = &errors[(x + 2) * 3..][..3];
+                        let offset = match self.dither {
+                            Dither::Bayer => {
+                                let threshold = i32::from(BAYER[y % 8][x % 8]) * 2 + 1 - 64;
+                                threshold * strength / 256 / 4
+                            }
+                            _ => 0,
+                        };
+                        let mut color = [0u8; 3];
+                        for c in 0..3 {
+                            let diffused = error[c] * strength / (divisor * 256);
+                            color[c] =
+                                (i32::from(pixel[c]) + diffused + offset).clamp(0, 255) as u8;
+                        }
+                        let index = nearest.index_of(color);
+                        let entry = &palette[usize::from(index) * 3..][..3];
+                        for c in 0..3 {
+                            let error = i32::from(color[c]) - i32::from(entry[c]);
+                            for &(dx, dy, weight) in kernel {
+                                let dx = if reverse { -dx } else { dx };
+                                let nx = (x + 2).wrapping_add_signed(dx);
+                                errors[dy * stride + nx * 3 + c] += error * weight;
+                            }
+                        }
+                        index
+                    }
+                };
+            }
+            errors.copy_within(stride.., 0);
+            errors[stride * 2..].fill(0);
+        }
+        buffer
+    }
+}
+
+impl Default for FrameOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One global palette for all frames of an animation.
+///
+/// The palette is computed from all frames, or a sample of them, and can be passed to
+/// [`Encoder::new`](crate::Encoder::new). [`frame`](Self::frame) then maps each frame to it,
+/// creating frames without a local palette. Frames that the palette represents too poorly can
+/// fall back to a local palette, see [`set_max_error`](Self::set_max_error).
+#[derive(Clone, Debug)]
+pub struct AnimationPalette<Q> {
+    quantizer: Q,
+    options: FrameOptions,
+    palette: Vec<u8>,
+    transparent: Option<u8>,
+    max_error: Option<u32>,
+    /// Pixels and mapped frame of the previous frame that used the global palette.
+    previous: Option<(Vec<u8>, Frame<'static>)>,
+}
+
+impl<Q: Quantizer> AnimationPalette<Q> {
+    /// Computes the palette for the RGBA pixels of `frames`.
+    ///
+    /// If the frames have at most 256 colors the palette is exact, otherwise it is reduced by the
+    /// `quantizer`. If any pixel is transparent, the last entry is reserved for transparency.
+    pub fn new(quantizer: Q, options: FrameOptions, frames: &[&[u8]]) -> Self {
+        // Alpha dithering depends on the position, sample with an average cutoff
+        let cutoff = if options.alpha_dither {
+            0x80
+        } else {
+            options.alpha_threshold
+        };
+        let pixels: Vec<u8> = frames
+            .iter()
+            .flat_map(|frame| frame.chunks_exact(4))
+            .flat_map(|p| options.prepare_pixel([p[0], p[1], p[2], p[3]], cutoff))
+            .collect();
+        let transparent = pixels.chunks_exact(4).any(|pixel| pixel[3] == 0)
+            || options.alpha_dither
+                && frames
+                    .iter()
+                    .any(|frame| frame.chunks_exact(4).any(|pixel| pixel[3] < 0xFE));
+        let max_colors = if transparent { 255 } else { 256 };
+
+        let mut colors: BTreeSet<[u8; 3]> = BTreeSet::new();
+        for pixel in pixels.chunks_exact(4).filter(|pixel| pixel[3] != 0) {
+            if colors.insert([pixel[0], pixel[1], pixel[2]]) && colors.len() > max_colors {
+                break;
+            }
+        }
+        let mut palette: Vec<u8> = if colors.len() <= max_colors {
+            colors.into_iter().flatten().collect()
+        } else {
+            let mut palette = quantizer.palette(&pixels, max_colors);
+            palette.truncate((palette.len() / 3).min(max_colors) * 3);
+            palette
+        };
+        if palette.is_empty() {
+            palette.extend_from_slice(&[0, 0, 0]);
+        }
+        let transparent = transparent.then(|| {
+            palette.extend_from_slice(&[0, 0, 0]);
+            (palette.len() / 3 - 1) as u8
+        });
+
+        Self {
+            quantizer,
+            options,
+            palette,
+            transparent,
+            max_error: None,
+            previous: None,
+        }
+    }
+
+    /// The global palette, as RGB triples.
+    #[must_use]
+    pub fn palette(&self) -> &[u8] {
+        &self.palette
+    }
+
+    /// The palette index for transparent pixels, if the frames had any.
+    #[must_use]
+    pub fn transparent(&self) -> Option<u8> {
+        self.transparent
+    }
+
+    /// Configure the largest mean squared error per color channel of a frame using the global
+    /// palette.
+    ///
+    /// Frames with a higher error get a local palette instead. By default frames always use the
+    /// global palette.
+    pub fn set_max_error(&mut self, max_error: Option<u32>) {
+        self.max_error = max_error;
+    }
+
+    /// Creates a frame from pixels in RGBA format, using the global palette if possible.
+    ///
+    /// The alpha options are applied in place, leaving alpha at 0 or 0xFF. Pixels that did not
+    /// change since the previous frame keep
+    /// their index. A local palette is used if the frame exceeds the maximum error, or has
+    /// transparent pixels while the global palette has no transparent index.
+    ///
+    /// # Panics:
+    /// *   If the length of pixels does not equal `width * height * 4`.
+    #[track_caller]
+    pub fn frame(&mut self, width: u16, height: u16, pixels: &mut [u8]) -> Frame<'static> {
+        unwrap_frame(self.try_frame(width, height, pixels))
+    }
+
+    /// Creates a frame from pixels in RGBA format, using the global palette if possible.
+    ///
+    /// See [`frame`](Self::frame).
+    ///
+    /// # Errors:
+    /// *   If the length of pixels does not equal `width * height * 4`. The pixels and the
+    ///     previous frame are left unchanged.
+    pub fn try_frame(
+        &mut self,
+        width: u16,
+        height: u16,
+        pixels: &mut [u8],
+    ) -> Result<Frame<'static>, EncodingError> {
+        check_pixels_len(width, height, 4, pixels)?;
+        self.options.apply_alpha(usize::from(width), pixels);
+        let has_transparency = pixels.chunks_exact(4).any(|pixel| pixel[3] == 0);
+
+        if !has_transparency || self.transparent.is_some() {
+            let previous = self
+                .previous
+                .as_ref()
+                .filter(|(_, frame)| frame.width == width && frame.height == height)
+                .map(|(pixels, frame)| (&pixels[..], &*frame.buffer));
+            let buffer = self.options.map(
+                usize::from(width),
+                pixels,
+                &self.palette,
+                self.transparent,
+                previous,
+            );
+            let frame = Frame {
+                width,
+                height,
+                buffer: Cow::Owned(buffer),
+                transparent: if has_transparency {
+                    self.transparent
+                } else {
+                    None
+                },
+                ..Frame::default()
+            };
+            let error = mean_squared_error(pixels, &frame.buffer, &self.palette);
+            if self
+                .max_error
+                .map_or(true, |max_error| error <= u64::from(max_error))
+            {
+                self.previous = Some((pixels.to_vec(), frame.clone()));
+                return Ok(frame);
+            }
+        }
+
+        self.previous = None;
+        self.options
+            .try_quantize_rgba(width, height, pixels, &self.quantizer)
+    }
+}
+
+/// Checks that a palette to map to has 1 to 256 colors.
+fn check_palette(palette: &[u8]) -> Result<(), EncodingError> {
+    if palette.len() < 3 {
+        return Err(EncodingFormatError::MissingColorPalette.into());
+    }
+    if palette.len() > 256 * 3 {
+        return Err(EncodingFormatError::TooManyColors.into());
+    }
+    Ok(())
+}
+
+/// Mean squared error per channel of the opaque pixels mapped to the palette.
+fn mean_squared_error(pixels: &[u8], buffer: &[u8], palette: &[u8]) -> u64 {
+    let (mut sum, mut count) = (0u64, 0u64);
+    for (pixel, &index) in pixels.chunks_exact(4).zip(buffer) {
+        if pixel[3] == 0 {
+            continue;
+        }
+        let entry = &palette[usize::from(index) * 3..][..3];
+        for (&a, &b) in pixel.iter().zip(entry) {
+            sum += u64::from(a.abs_diff(b)).pow(2);
+        }
+        count += 3;
+    }
+    sum / count.max(1)
+}
+
+/// Maps colors to the nearest entry of a palette.
+struct NearestColor<'a> {
+    palette: &'a [u8],
+    /// Index that is never returned, the transparent color.
+    skip: Option<u8>,
+    /// Direct mapped cache of `0x100_0000 | rgb` to the palette index.
+    cache: Vec<(u32, u8)>,
+}
+
+impl<'a> NearestColor<'a> {
+    const CACHE_SIZE: usize = 1 << 12;
+
+    fn new(palette: &'a [u8], skip: Option<u8>) -> Self {
+        Self {
+            palette,
+            skip,
+            cache: vec![(0, 0); Self::CACHE_SIZE],
+        }
+    }
+
+    fn index_of(&mut self, [r, g, b]: [u8; 3]) -> u8 {
+        let key = u32::from_be_bytes([1, r, g, b]);
+        let slot = (key.wrapping_mul(0x9E37_79B9) >> 20) as usize % Self::CACHE_SIZE;
+        if self.cache[slot].0 == key {
+            return self.cache[slot].1;
+        }
+        let index = self
+            .palette
+            .chunks_exact(3)
+            .enumerate()
+            .filter(|&(index, _)| self.skip != Some(index as u8))
+            .map(|(index, entry)| {
+                let d = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+                (index, d(entry[0], r) + d(entry[1], g) + d(entry[2], b))
+            })
+            .min_by_key(|&(_, distance)| distance)
+            .map_or(0, |(index, _)| index as u8);
+        self.cache[slot] = (key, index);
+        index
+    }
+}
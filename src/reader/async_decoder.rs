@@ -0,0 +1,259 @@
+use alloc::borrow::Cow;
+use core::future::poll_fn;
+use core::mem;
+use core::pin::Pin;
+use core::task::{ready, Poll};
+
+use futures_io::AsyncBufRead;
+
+use super::converter::FrameProgress;
+use super::{
+    Comment, DecodeOptions, Decoder, DecodingError, FrameDataType, PushInput, StreamingDecoder,
+};
+use crate::common::{Frame, PlainText};
+use crate::{PixelAspectRatio, Repeat};
+
+impl DecodeOptions {
+    /// Reads the logical screen descriptor including the global color palette from an
+    /// asynchronous reader.
+    ///
+    /// Returns an [`AsyncDecoder`]. All decoder configuration has to be done beforehand.
+    pub async fn read_info_async<R: AsyncBufRead + Unpin>(
+        self,
+        r: R,
+    ) -> Result<AsyncDecoder<R>, DecodingError> {
+        let decoder = Decoder::with_no_init(
+            PushInput::default(),
+            StreamingDecoder::with_options(&self),
+            self,
+        );
+        let mut decoder = AsyncDecoder { reader: r, decoder };
+        decoder.retry(|decoder| decoder.read_header()).await?;
+        Ok(decoder)
+    }
+}
+
+/// GIF decoder reading from a [`futures_io::AsyncBufRead`], without blocking.
+///
+/// Create it with [`AsyncDecoder::new`] or [`DecodeOptions::read_info_async`]. The interface
+/// matches [`Decoder`], except that methods reading input are `async`. Wrap tokio readers in
+/// `io::TokioCompat` when the `tokio` feature is enabled.
+///
+/// Dropping the future of [`Self::next_frame_info`] before it completes is safe, it can be
+/// called again to continue. Dropping the future of [`Self::read_next_frame`] or
+/// [`Self::read_into_buffer`] loses the pixels of the current frame.
+pub struct AsyncDecoder<R> {
+    reader: R,
+    decoder: Decoder<PushInput>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncDecoder<R> {
+    /// Create a new decoder with default options.
+    #[inline]
+    pub async fn new(reader: R) -> Result<Self, DecodingError> {
+        DecodeOptions::new().read_info_async(reader).await
+    }
+
+    /// Calls `op` until it succeeds or fails for another reason than missing input.
+    async fn retry<T>(
+        &mut self,
+        mut op: impl FnMut(&mut Decoder<PushInput>) -> Result<T, DecodingError>,
+    ) -> Result<T, DecodingError> {
+        loop {
+            match op(&mut self.decoder) {
+                Err(_) if self.decoder.decoder.reader.get_ref().starved => self.fill().await?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Moves the next chunk of the reader into the input of the decoder.
+    async fn fill(&mut self) -> Result<(), DecodingError> {
+        let reader = &mut self.reader;
+        let input = self.decoder.decoder.reader.get_mut();
+        poll_fn(|cx| {
+            let data = ready!(Pin::new(&mut *reader).poll_fill_buf(cx))?;
+            let len = data.len();
+            if len == 0 {
+                input.eof = true;
+                input.starved = false;
+            } else {
+                input.push(data);
+            }
+            Pin::new(&mut *reader).consume(len);
+            Poll::Ready(Ok(()))
+        })
+        .await
+    }
+
+    /// Returns the next frame info
+    pub async fn next_frame_info(&mut self) -> Result<Option<&Frame<'static>>, DecodingError> {
+        let found = self
+            .retry(|decoder| decoder.next_frame_info().map(|frame| frame.is_some()))
+            .await?;
+        Ok(found.then_some(&self.decoder.current_frame))
+    }
+
+    /// Query information about the frame previously advanced with [`Self::next_frame_info`].
+    ///
+    /// Returns `None` past the end of file.
+    #[inline]
+    pub fn current_frame_info(&self) -> Option<&Frame<'static>> {
+        self.decoder.current_frame_info()
+    }
+
+    /// Reads the next frame from the image.
+    ///
+    /// Do not call `Self::next_frame_info` beforehand.
+    /// Deinterlaces the result.
+    pub async fn read_next_frame(&mut self) -> Result<Option<&Frame<'static>>, DecodingError> {
+        if self.next_frame_info().await?.is_none() {
+            return Ok(None);
+        }
+        let frame = &mut self.decoder.current_frame;
+        match self.decoder.current_frame_data_type {
+            FrameDataType::Pixels => {
+                let pixel_bytes = self
+                    .decoder
+                    .pixel_converter
+                    .check_buffer_size(frame, &self.decoder.memory_limit)?;
+                let mut vec = mem::replace(&mut frame.buffer, Cow::Borrowed(&[])).into_owned();
+                vec.clear();
+                vec.resize(pixel_bytes, 0);
+                let mut progress = FrameProgress::new(frame);
+                self.retry(|decoder| {
+                    decoder.pixel_converter.read_into_buffer_resumable(
+                        &decoder.current_frame,
+                        &mut vec,
+                        &mut progress,
+                        false,
+                        &mut |out| decoder.decoder.decode_next_bytes(out),
+                    )
+                })
+                .await?;
+                self.decoder.current_frame.buffer = Cow::Owned(vec);
+                self.decoder.current_frame.interlaced = false;
+            }
+            FrameDataType::Lzw { min_code_size } => {
+                let mut vec = self.decoder.take_lzw_buffer()?;
+                // `write_lzw_pre_encoded_frame` smuggles `min_code_size` in the first byte.
+                vec.push(min_code_size);
+                self.retry(|decoder| decoder.copy_lzw_data(&mut vec))
+                    .await?;
+                self.decoder.current_frame.buffer = Cow::Owned(vec);
+            }
+        }
+        Ok(Some(&self.decoder.current_frame))
+    }
+
+    /// Reads the data of the current frame into a pre-allocated buffer.
+    ///
+    /// `Self::next_frame_info` needs to be called beforehand.
+    /// The length of `buf` must be at least `Self::buffer_size`.
+    /// Deinterlaces the result.
+    pub async fn read_into_buffer(&mut self, buf: &mut [u8]) -> Result<(), DecodingError> {
+        let mut progress = FrameProgress::new(&self.decoder.current_frame);
+        self.retry(|decoder| {
+            decoder.pixel_converter.read_into_buffer_resumable(
+                &decoder.current_frame,
+                buf,
+                &mut progress,
+                false,
+                &mut |out| decoder.decoder.decode_next_bytes(out),
+            )
+        })
+        .await
+    }
+
+    /// Output buffer size
+    #[inline]
+    pub fn buffer_size(&self) -> usize {
+        self.decoder.buffer_size()
+    }
+
+    /// Line length of the current frame
+    #[inline]
+    pub fn line_length(&self) -> usize {
+        self.decoder.line_length()
+    }
+
+    /// Returns the color palette relevant for the frame that has been decoded
+    #[inline]
+    pub fn palette(&self) -> Result<&[u8], DecodingError> {
+        self.decoder.palette()
+    }
+
+    /// The global color palette
+    #[inline]
+    pub fn global_palette(&self) -> Option<&[u8]> {
+        self.decoder.global_palette()
+    }
+
+    /// Width of the image
+    #[inline]
+    pub fn width(&self) -> u16 {
+        self.decoder.width()
+    }
+
+    /// Height of the image
+    #[inline]
+    pub fn height(&self) -> u16 {
+        self.decoder.height()
+    }
+
+    /// Pixel aspect ratio from the logical screen descriptor.
+    #[inline]
+    pub fn pixel_aspect_ratio(&self) -> PixelAspectRatio {
+        self.decoder.pixel_aspect_ratio()
+    }
+
+    /// Index of the background color in the global palette
+    #[inline]
+    pub fn bg_color(&self) -> Option<usize> {
+        self.decoder.bg_color()
+    }
+
+    /// Number of loop repetitions
+    #[inline]
+    pub fn repeat(&self) -> Repeat {
+        self.decoder.repeat()
+    }
+
+    /// XMP metadata stored in the image.
+    #[inline]
+    pub fn xmp_metadata(&self) -> Option<&[u8]> {
+        self.decoder.xmp_metadata()
+    }
+
+    /// ICC profile stored in the image.
+    #[inline]
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.decoder.icc_profile()
+    }
+
+    /// Comment extensions read so far, see [`Decoder::comments`].
+    #[inline]
+    pub fn comments(&self) -> &[Comment] {
+        self.decoder.comments()
+    }
+
+    /// Comment extensions of the current frame, see [`Decoder::frame_comments`].
+    #[inline]
+    pub fn frame_comments(&self) -> &[Comment] {
+        self.decoder.frame_comments()
+    }
+
+    /// Plain text extensions of the current frame, see [`Decoder::frame_plain_text`].
+    #[inline]
+    pub fn frame_plain_text(&self) -> &[PlainText] {
+        self.decoder.frame_plain_text()
+    }
+
+    /// Abort decoding and recover the reader.
+    ///
+    /// Input that has already been read from it but not decoded yet is lost.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
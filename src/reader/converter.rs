@@ -4,6 +4,7 @@ use core::iter;
 use core::mem;
 
 use super::decoder::{DecodingError, OutputBuffer, PLTE_CHANNELS};
+use super::DecodedRow;
 use crate::common::Frame;
 use crate::MemoryLimit;
 
@@ -12,6 +13,7 @@ pub(crate) const N_CHANNELS: usize = 4;
 /// Output mode for the image data
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
+#[non_exhaustive]
 pub enum ColorOutput {
     /// The decoder expands the image data to 32bit RGBA.
     /// This affects:
@@ -21,12 +23,41 @@ pub enum ColorOutput {
     RGBA = 0,
     /// The decoder returns the raw indexed data.
     Indexed = 1,
+    /// The decoder expands the image data to 24bit RGB.
+    ///
+    /// Transparency is lost, transparent pixels have the color of their palette entry.
+    RGB = 2,
+    /// The decoder expands the image data to 32bit BGRA, e.g. for GPU upload.
+    BGRA = 3,
+    /// The decoder expands the image data to 32bit RGBA with premultiplied alpha, as used by
+    /// Cairo and most compositors. Transparent pixels are all zero.
+    PremultipliedRGBA = 4,
+    /// The decoder converts the image data to 8bit grayscale, using Rec. 709 luma coefficients.
+    ///
+    /// Transparency is lost, transparent pixels have the luma of their palette entry.
+    Luma = 5,
+    /// The decoder converts the image data to 8bit grayscale followed by 8bit alpha.
+    LumaAlpha = 6,
+}
+
+impl ColorOutput {
+    /// Number of bytes of each pixel in this output format.
+    #[inline]
+    #[must_use]
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Indexed | Self::Luma => 1,
+            Self::LumaAlpha => 2,
+            Self::RGB => 3,
+            Self::RGBA | Self::BGRA | Self::PremultipliedRGBA => N_CHANNELS,
+        }
+    }
 }
 
 pub(crate) type FillBufferCallback<'a> =
     &'a mut dyn FnMut(&mut OutputBuffer<'_>) -> Result<usize, DecodingError>;
 
-/// Deinterlaces and expands to RGBA if needed
+/// Deinterlaces and expands to the color output if needed
 pub(crate) struct PixelConverter {
     color_output: ColorOutput,
     buffer: Vec<u8>,
@@ -89,11 +120,7 @@ impl PixelConverter {
 
     #[inline]
     pub(crate) const fn line_length(&self, frame: &Frame<'_>) -> usize {
-        use self::ColorOutput::{Indexed, RGBA};
-        match self.color_output {
-            RGBA => frame.width as usize * N_CHANNELS,
-            Indexed => frame.width as usize,
-        }
+        frame.width as usize * self.color_output.bytes_per_pixel()
     }
 
     /// Use `read_into_buffer` to deinterlace
@@ -104,14 +131,15 @@ impl PixelConverter {
         mut buf: &mut [u8],
         data_callback: FillBufferCallback<'_>,
     ) -> Result<bool, DecodingError> {
+        let bytes_per_pixel = self.color_output.bytes_per_pixel();
         loop {
             let decode_into = match self.color_output {
                 // When decoding indexed data, LZW can write the pixels directly
                 ColorOutput::Indexed => &mut buf[..],
-                // When decoding RGBA, the pixel data will be expanded by a factor of 4,
+                // When converting, the pixel data will be expanded to a different size,
                 // and it's simpler to decode indexed pixels to another buffer first
-                ColorOutput::RGBA => {
-                    let buffer_size = buf.len() / N_CHANNELS;
+                _ => {
+                    let buffer_size = buf.len() / bytes_per_pixel;
                     if buffer_size == 0 {
                         return Err(DecodingError::format("odd-sized buffer"));
                     }
@@ -124,44 +152,21 @@ impl PixelConverter {
             match data_callback(&mut OutputBuffer::Slice(decode_into))? {
                 0 => return Ok(false),
                 bytes_decoded => {
-                    match self.color_output {
-                        ColorOutput::RGBA => {
-                            let transparent = current_frame.transparent;
-                            let palette: &[u8] = current_frame
-                                .palette
-                                .as_deref()
-                                .or(self.global_palette.as_deref())
-                                .unwrap_or_default(); // next_frame_info already checked it won't happen
-
-                            let (pixels, rest) = buf.split_at_mut(bytes_decoded * N_CHANNELS);
-                            buf = rest;
-
-                            for (rgba, idx) in pixels
-                                .chunks_exact_mut(N_CHANNELS)
-                                .zip(self.buffer.iter().copied().take(bytes_decoded))
-                            {
-                                let plte_offset = PLTE_CHANNELS * idx as usize;
-                                if let Some(colors) =
-                                    palette.get(plte_offset..plte_offset + PLTE_CHANNELS)
-                                {
-                                    rgba[0] = colors[0];
-                                    rgba[1] = colors[1];
-                                    rgba[2] = colors[2];
-                                    rgba[3] = if let Some(t) = transparent {
-                                        if t == idx {
-                                            0x00
-                                        } else {
-                                            0xFF
-                                        }
-                                    } else {
-                                        0xFF
-                                    };
-                                }
-                            }
-                        }
-                        ColorOutput::Indexed => {
-                            buf = &mut buf[bytes_decoded..];
-                        }
+                    let (pixels, rest) = buf.split_at_mut(bytes_decoded * bytes_per_pixel);
+                    buf = rest;
+                    if self.color_output != ColorOutput::Indexed {
+                        let palette: &[u8] = current_frame
+                            .palette
+                            .as_deref()
+                            .or(self.global_palette.as_deref())
+                            .unwrap_or_default(); // next_frame_info already checked it won't happen
+                        expand_pixels(
+                            self.color_output,
+                            pixels,
+                            &self.buffer[..bytes_decoded],
+                            palette,
+                            current_frame.transparent,
+                        );
                     }
                     if buf.is_empty() {
                         return Ok(true);
@@ -171,6 +176,11 @@ impl PixelConverter {
         }
     }
 
+    #[inline]
+    pub(crate) const fn color_output(&self) -> ColorOutput {
+        self.color_output
+    }
+
     pub(crate) fn global_palette(&self) -> Option<&[u8]> {
         self.global_palette.as_deref()
     }
@@ -221,9 +231,305 @@ impl PixelConverter {
         };
         Ok(())
     }
+
+    /// Like `read_into_buffer`, but writes each row at `offset + row * stride`
+    pub(crate) fn read_into_buffer_strided(
+        &mut self,
+        frame: &Frame<'_>,
+        buf: &mut [u8],
+        offset: usize,
+        stride: usize,
+        data_callback: FillBufferCallback<'_>,
+    ) -> Result<(), DecodingError> {
+        let width = self.line_length(frame);
+        if stride < width {
+            return Err(DecodingError::format("stride is smaller than a line"));
+        }
+        let height = usize::from(frame.height);
+        // Handle a too-small buffer and 32-bit usize overflow without panicking
+        let len = match height.checked_sub(1) {
+            Some(rows) => rows.checked_mul(stride).and_then(|n| n.checked_add(width)),
+            None => Some(0),
+        };
+        let buf = len
+            .and_then(|len| offset.checked_add(len))
+            .and_then(|end| buf.get_mut(offset..end))
+            .ok_or_else(|| DecodingError::format("buffer too small"))?;
+        if stride == width || width == 0 {
+            // Rows are packed
+            return self.read_into_buffer(frame, buf, data_callback);
+        }
+
+        for row in RowOrder::new(frame.height, frame.interlaced) {
+            let line = &mut buf[row * stride..][..width];
+            if !self.fill_buffer(frame, line, data_callback)? {
+                return Err(DecodingError::format("image truncated"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `read_into_buffer`, but calls `on_row` after each row has been decoded
+    ///
+    /// With `fill_blocks`, rows of interlaced frames are also copied over the following rows
+    /// that belong to later passes.
+    pub(crate) fn read_into_buffer_progressive(
+        &mut self,
+        frame: &Frame<'_>,
+        buf: &mut [u8],
+        fill_blocks: bool,
+        data_callback: FillBufferCallback<'_>,
+        on_row: &mut dyn FnMut(DecodedRow, &[u8]),
+    ) -> Result<(), DecodingError> {
+        let width = self.line_length(frame);
+        let buf = self
+            .buffer_size(frame)
+            .and_then(|buffer_size| buf.get_mut(..buffer_size))
+            .ok_or_else(|| DecodingError::format("buffer too small"))?;
+        if width == 0 {
+            return Ok(());
+        }
+        let height = usize::from(frame.height);
+        for y in RowOrder::new(frame.height, frame.interlaced) {
+            let (line, below) = buf[y * width..].split_at_mut(width);
+            if !self.fill_buffer(frame, line, data_callback)? {
+                return Err(DecodingError::format("image truncated"));
+            }
+            let pass = frame.interlaced.then(|| interlace_pass(y));
+            let rows = match pass {
+                Some(pass) if fill_blocks => fill_interlaced_block(line, below, pass, height - y),
+                _ => 1,
+            };
+            on_row(
+                DecodedRow {
+                    y: y as u16,
+                    pass,
+                    rows: rows as u16,
+                },
+                buf,
+            );
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    /// Like `read_into_buffer`, but can continue where it stopped if `data_callback` failed
+    ///
+    /// `fill_blocks` works like in `read_into_buffer_progressive`.
+    pub(crate) fn read_into_buffer_resumable(
+        &mut self,
+        frame: &Frame<'_>,
+        buf: &mut [u8],
+        progress: &mut FrameProgress,
+        fill_blocks: bool,
+        data_callback: FillBufferCallback<'_>,
+    ) -> Result<(), DecodingError> {
+        let width = self.line_length(frame);
+        if !self
+            .buffer_size(frame)
+            .is_some_and(|buffer_size| buf.len() >= buffer_size)
+        {
+            return Err(DecodingError::format("buffer too small"));
+        }
+        if width == 0 {
+            return Ok(());
+        }
+        let bytes_per_pixel = self.color_output.bytes_per_pixel();
+        let height = usize::from(frame.height);
+        while let Some(row) = progress.row {
+            let (line, below) = buf[row * width..].split_at_mut(width);
+            // Pixels are expanded before the callback is called again, so all decoded pixels
+            // have been written when it fails
+            let mut decoded = 0;
+            let result = self.fill_buffer(frame, &mut line[progress.filled..], &mut |out| {
+                let n = data_callback(out)?;
+                decoded += n;
+                Ok(n)
+            });
+            match result {
+                Ok(true) => {
+                    if fill_blocks && frame.interlaced {
+                        fill_interlaced_block(line, below, interlace_pass(row), height - row);
+                    }
+                    progress.next_row();
+                }
+                Ok(false) => return Err(DecodingError::format("image truncated")),
+                Err(err) => {
+                    progress.filled += decoded * bytes_per_pixel;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+/// Position within a frame whose data is read over several calls, when the data callback fails
+/// only because input is not available yet.
+pub(crate) struct FrameProgress {
+    rows: RowOrder,
+    /// Row being filled, `None` once all rows are complete
+    row: Option<usize>,
+    /// Bytes of the row that are already filled
+    filled: usize,
+    /// Number of complete rows
+    rows_done: usize,
+}
+
+#[cfg(feature = "std")]
+impl FrameProgress {
+    pub(crate) fn new(frame: &Frame<'_>) -> Self {
+        let mut rows = RowOrder::new(frame.height, frame.interlaced);
+        Self {
+            row: rows.next(),
+            rows,
+            filled: 0,
+            rows_done: 0,
+        }
+    }
+
+    fn next_row(&mut self) {
+        self.filled = 0;
+        self.rows_done += 1;
+        self.row = self.rows.next();
+    }
+
+    /// Number of complete rows
+    pub(crate) fn rows_done(&self) -> usize {
+        self.rows_done
+    }
+
+    /// Number of bytes of the output that have been decoded
+    pub(crate) fn decoded_len(&self, line_length: usize) -> usize {
+        self.rows_done * line_length + self.filled
+    }
+}
+
+/// Converts palette indices to pixels of the color output.
+fn expand_pixels(
+    color_output: ColorOutput,
+    pixels: &mut [u8],
+    indices: &[u8],
+    palette: &[u8],
+    transparent: Option<u8>,
+) {
+    #[inline(always)]
+    fn expand<const N: usize>(
+        pixels: &mut [u8],
+        indices: &[u8],
+        palette: &[u8],
+        transparent: Option<u8>,
+        convert: impl Fn([u8; PLTE_CHANNELS], bool) -> [u8; N],
+    ) {
+        for (pixel, &idx) in pixels.chunks_exact_mut(N).zip(indices) {
+            let plte_offset = PLTE_CHANNELS * idx as usize;
+            if let Some(colors) = palette.get(plte_offset..plte_offset + PLTE_CHANNELS) {
+                let opaque = transparent != Some(idx);
+                pixel.copy_from_slice(&convert([colors[0], colors[1], colors[2]], opaque));
+            }
+        }
+    }
+
+    let alpha = |opaque: bool| if opaque { 0xFF } else { 0x00 };
+    let luma = |[r, g, b]: [u8; PLTE_CHANNELS]| {
+        ((2126 * u32::from(r) + 7152 * u32::from(g) + 722 * u32::from(b) + 5000) / 10000) as u8
+    };
+    match color_output {
+        ColorOutput::RGBA => expand(
+            pixels,
+            indices,
+            palette,
+            transparent,
+            |[r, g, b], opaque| [r, g, b, alpha(opaque)],
+        ),
+        ColorOutput::BGRA => expand(
+            pixels,
+            indices,
+            palette,
+            transparent,
+            |[r, g, b], opaque| [b, g, r, alpha(opaque)],
+        ),
+        ColorOutput::PremultipliedRGBA => {
+            // Alpha is either 0 or 0xFF, so only transparent pixels change
+            expand(
+                pixels,
+                indices,
+                palette,
+                transparent,
+                |[r, g, b], opaque| {
+                    if opaque {
+                        [r, g, b, 0xFF]
+                    } else {
+                        [0; N_CHANNELS]
+                    }
+                },
+            );
+        }
+        ColorOutput::RGB => expand(pixels, indices, palette, transparent, |rgb, _| rgb),
+        ColorOutput::Luma => expand(pixels, indices, palette, transparent, |rgb, _| [luma(rgb)]),
+        ColorOutput::LumaAlpha => expand(pixels, indices, palette, transparent, |rgb, opaque| {
+            [luma(rgb), alpha(opaque)]
+        }),
+        ColorOutput::Indexed => {}
+    }
+}
+
+/// Copies a row of an interlaced frame over the rows `below` it that belong to later passes.
+///
+/// Returns the number of rows covered by the row, including itself.
+fn fill_interlaced_block(line: &[u8], below: &mut [u8], pass: u8, rows_left: usize) -> usize {
+    // Rows of the first pass are 8 apart, of the second 4, and so on
+    let rows = (8 >> (pass - 1)).min(rows_left);
+    for next in below.chunks_exact_mut(line.len()).take(rows - 1) {
+        next.copy_from_slice(line);
+    }
+    rows
+}
+
+/// Interlacing pass of a row, from 1 to 4.
+fn interlace_pass(y: usize) -> u8 {
+    match y % 8 {
+        0 => 1,
+        4 => 2,
+        2 | 6 => 3,
+        _ => 4,
+    }
+}
+
+/// Rows of a frame in the order they are stored in the file.
+pub(crate) enum RowOrder {
+    Sequential(core::ops::Range<usize>),
+    Interlaced(InterlaceIterator),
+}
+
+impl RowOrder {
+    pub(crate) fn new(height: u16, interlaced: bool) -> Self {
+        if interlaced {
+            Self::Interlaced(InterlaceIterator {
+                len: height,
+                next: 0,
+                pass: 0,
+            })
+        } else {
+            Self::Sequential(0..usize::from(height))
+        }
+    }
+}
+
+impl iter::Iterator for RowOrder {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Sequential(rows) => rows.next(),
+            Self::Interlaced(rows) => rows.next(),
+        }
+    }
 }
 
-struct InterlaceIterator {
+pub(crate) struct InterlaceIterator {
     len: u16,
     next: usize,
     pass: usize,
@@ -8,7 +8,7 @@ use core::default::Default;
 use core::mem;
 use core::num::NonZeroUsize;
 
-use crate::common::{AnyExtension, Block, DisposalMethod, Extension, Frame};
+use crate::common::{AnyExtension, Block, DisposalMethod, Extension, Frame, PixelAspectRatio};
 use crate::io;
 use crate::reader::DecodeOptions;
 use crate::MemoryLimit;
@@ -150,6 +150,8 @@ pub enum Decoded {
     GlobalPalette(Box<[u8]>),
     /// Index of the background color in the global palette.
     BackgroundColor(u8),
+    /// Pixel aspect ratio from the logical screen descriptor.
+    PixelAspectRatio(PixelAspectRatio),
     /// Palette and optional `Application` extension have been parsed,
     /// reached frame data.
     HeaderEnd,
@@ -184,11 +186,15 @@ pub enum Decoded {
 enum State {
     Magic,
     ScreenDescriptor,
+    /// Emits the aspect ratio, then continues with the global palette of the given size
+    AspectRatio(usize),
     ImageBlockStart,
     GlobalPalette(usize),
     BlockStart(u8),
     BlockEnd,
     ExtensionBlockStart,
+    /// Extension without any data sub-blocks
+    ExtensionDataEmpty,
     /// Resets ext.data
     ExtensionDataSubBlockStart(usize),
     /// Collects data in ext.data
@@ -350,7 +356,7 @@ impl LzwReader {
         let status = match status? {
             ok @ LzwStatus::Done | ok @ LzwStatus::Ok => ok,
             ok @ LzwStatus::NoProgress => {
-                if self.check_for_end_code {
+                if self.check_for_end_code && consumed_out == 0 {
                     return Err(DecodingError::EndCodeNotFound);
                 }
 
@@ -378,6 +384,7 @@ pub struct StreamingDecoder {
     version: Version,
     width: u16,
     height: u16,
+    pixel_aspect_ratio: PixelAspectRatio,
     global_color_table: Vec<u8>,
     /// ext buffer
     ext: ExtensionData,
@@ -461,6 +468,7 @@ impl StreamingDecoder {
             version: Version::V87a,
             width: 0,
             height: 0,
+            pixel_aspect_ratio: PixelAspectRatio::UNSPECIFIED,
             global_color_table: Vec::new(),
             ext: ExtensionData {
                 id: AnyExtension(0),
@@ -535,6 +543,12 @@ impl StreamingDecoder {
         self.height
     }
 
+    /// Pixel aspect ratio of the image
+    #[must_use]
+    pub fn pixel_aspect_ratio(&self) -> PixelAspectRatio {
+        self.pixel_aspect_ratio
+    }
+
     /// The version number of the GIF standard used in this image.
     ///
     /// We suppose a minimum of `V87a` compatibility. This value will be reported until we have
@@ -615,6 +629,7 @@ impl StreamingDecoder {
                 self.height = u16::from_le_bytes(desc[2..4].try_into().unwrap());
                 let global_flags = desc[4];
                 let background_color = desc[5];
+                self.pixel_aspect_ratio = PixelAspectRatio(desc[6]);
 
                 let global_table = global_flags & 0x80 != 0;
                 let table_size = if global_table {
@@ -629,10 +644,17 @@ impl StreamingDecoder {
 
                 goto!(
                     consumed,
-                    GlobalPalette(table_size),
+                    AspectRatio(table_size),
                     emit Decoded::BackgroundColor(background_color)
                 )
             }
+            AspectRatio(table_size) => {
+                goto!(
+                    0,
+                    GlobalPalette(table_size),
+                    emit Decoded::PixelAspectRatio(self.pixel_aspect_ratio)
+                )
+            }
             ImageBlockStart => {
                 let (consumed, header) = ensure_min_length_buffer!(9);
 
@@ -720,7 +742,20 @@ impl StreamingDecoder {
                 }
             }
             ExtensionBlockStart => {
-                goto!(ExtensionDataSubBlockStart(b as usize), emit Decoded::BlockStart(Block::Extension))
+                // A zero length is the block terminator, not the length of a first sub-block
+                let next = if b == 0 {
+                    ExtensionDataEmpty
+                } else {
+                    ExtensionDataSubBlockStart(b as usize)
+                };
+                goto!(next, emit Decoded::BlockStart(Block::Extension))
+            }
+            ExtensionDataEmpty => {
+                self.ext.data.clear();
+                if self.ext.id.into_known() == Some(Extension::Control) {
+                    self.read_control_extension()?;
+                }
+                goto!(0, ExtensionBlockEnd, emit Decoded::SubBlock { ext: self.ext.id, is_last: true })
             }
             ExtensionBlockEnd => {
                 self.ext.data.clear();
@@ -806,8 +841,9 @@ impl StreamingDecoder {
                     let (mut consumed, bytes_len, status) =
                         self.lzw_reader.decode_bytes(&buf[..n], write_into)?;
 
-                    // skip if can't make progress (decode would fail if check_for_end_code was set)
-                    if matches!(status, LzwStatus::NoProgress) {
+                    // skip if can't make progress (decode would fail if check_for_end_code was set).
+                    // Output that was pending in the LZW decoder is progress, the input is kept.
+                    if matches!(status, LzwStatus::NoProgress) && bytes_len == 0 {
                         consumed = n;
                     }
 
@@ -861,6 +897,29 @@ impl StreamingDecoder {
         Ok(())
     }
 
+    /// Restarts decoding at the GIF header, keeping the options.
+    #[cfg(feature = "std")]
+    pub(crate) fn rewind(&mut self) {
+        self.state = Magic;
+        self.unused_internal_buffer_len = 0;
+        self.global_color_table.clear();
+        self.ext.data.clear();
+        self.current = None;
+        self.header_end_reached = false;
+    }
+
+    /// Continues decoding right after the introducer of an image descriptor.
+    ///
+    /// `frame` carries the metadata of the control extension preceding the image.
+    #[cfg(feature = "std")]
+    pub(crate) fn seek_to_image(&mut self, frame: Frame<'static>) {
+        self.state = BlockStart(Block::Image as u8);
+        self.unused_internal_buffer_len = 0;
+        self.ext.data.clear();
+        self.current = Some(frame);
+        self.header_end_reached = true;
+    }
+
     fn add_frame(&mut self) {
         if self.current.is_none() {
             self.current = Some(Frame::default());
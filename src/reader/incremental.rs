@@ -0,0 +1,338 @@
+use alloc::borrow::Cow;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::mem;
+
+use super::converter::FrameProgress;
+use super::{
+    Comment, DecodeOptions, Decoder, DecodingError, FrameDataType, PushInput, StreamingDecoder,
+};
+use crate::common::Frame;
+use crate::{PixelAspectRatio, Repeat};
+
+impl DecodeOptions {
+    /// Creates an [`IncrementalDecoder`] that is fed with input as it arrives.
+    ///
+    /// All decoder configuration has to be done beforehand.
+    #[must_use]
+    pub fn incremental(self) -> IncrementalDecoder {
+        let decoder = Decoder::with_no_init(
+            PushInput::default(),
+            StreamingDecoder::with_options(&self),
+            self,
+        );
+        IncrementalDecoder {
+            decoder,
+            state: State::Header,
+            frames: VecDeque::new(),
+            frames_decoded: 0,
+        }
+    }
+}
+
+/// What the decoder is waiting for.
+enum State {
+    /// The logical screen descriptor and global palette
+    Header,
+    /// The metadata of the next frame
+    FrameInfo,
+    /// The pixels of the current frame
+    Pixels {
+        buffer: Vec<u8>,
+        progress: FrameProgress,
+    },
+    /// The LZW data of the current frame, with `DecodeOptions::skip_frame_decoding`
+    Lzw { buffer: Vec<u8> },
+    /// Nothing, the end of the file has been reached
+    Done,
+}
+
+/// GIF decoder that is pushed input as it arrives, without blocking.
+///
+/// Create it with [`IncrementalDecoder::new`] or [`DecodeOptions::incremental`]. Each call to
+/// [`Self::push`] decodes as much of the image as the input received so far allows. Frames that
+/// are complete can be taken with [`Self::next_frame`], and the frame being decoded can be
+/// shown while it arrives with [`Self::partial_frame_buffer`].
+///
+/// ```
+/// # fn main() -> Result<(), gif::DecodingError> {
+/// # let data: &[u8] = include_bytes!("../../tests/samples/sample_1.gif");
+/// let mut decoder = gif::IncrementalDecoder::new();
+/// for chunk in data.chunks(100) {
+///     decoder.push(chunk)?;
+///     if let Some(buffer) = decoder.partial_frame_buffer() {
+///         // Draw the rows decoded so far
+///     }
+///     while let Some(frame) = decoder.next_frame() {
+///         // Draw the complete frame
+///     }
+/// }
+/// decoder.finish()?;
+/// # Ok(()) }
+/// ```
+pub struct IncrementalDecoder {
+    decoder: Decoder<PushInput>,
+    state: State,
+    /// Complete frames that have not been taken yet
+    frames: VecDeque<Frame<'static>>,
+    frames_decoded: usize,
+}
+
+impl IncrementalDecoder {
+    /// Create a new decoder with default options.
+    #[must_use]
+    #[inline]
+    pub fn new() -> Self {
+        DecodeOptions::new().incremental()
+    }
+
+    /// Decodes the next chunk of input.
+    ///
+    /// Returns once all input has been used. Errors are returned as soon as they are found,
+    /// decoding can't continue afterwards.
+    pub fn push(&mut self, data: &[u8]) -> Result<(), DecodingError> {
+        self.decoder.decoder.reader.get_mut().push(data);
+        self.decode()
+    }
+
+    /// Signals the end of input, and decodes what is left of it.
+    ///
+    /// Returns an error if the input ends within the header or a frame. A missing trailer is
+    /// accepted, like by [`Decoder`].
+    pub fn finish(&mut self) -> Result<(), DecodingError> {
+        let input = self.decoder.decoder.reader.get_mut();
+        input.eof = true;
+        input.starved = false;
+        self.decode()
+    }
+
+    /// Decodes until more input is needed.
+    fn decode(&mut self) -> Result<(), DecodingError> {
+        loop {
+            match self.step() {
+                Ok(true) => {}
+                Ok(false) => return Ok(()),
+                Err(_) if self.decoder.decoder.reader.get_ref().starved => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Advances the state, returns `false` at the end of the file.
+    fn step(&mut self) -> Result<bool, DecodingError> {
+        let decoder = &mut self.decoder;
+        match &mut self.state {
+            State::Header => {
+                decoder.read_header()?;
+                self.state = State::FrameInfo;
+            }
+            State::FrameInfo => {
+                if decoder.next_frame_info()?.is_none() {
+                    self.state = State::Done;
+                    return Ok(false);
+                }
+                self.state = match decoder.current_frame_data_type {
+                    FrameDataType::Pixels => {
+                        let frame = &decoder.current_frame;
+                        let len = decoder
+                            .pixel_converter
+                            .check_buffer_size(frame, &decoder.memory_limit)?;
+                        let mut buffer = Vec::new();
+                        buffer
+                            .try_reserve_exact(len)
+                            .map_err(|_| DecodingError::OutOfMemory)?;
+                        buffer.resize(len, 0);
+                        let progress = FrameProgress::new(frame);
+                        State::Pixels { buffer, progress }
+                    }
+                    FrameDataType::Lzw { min_code_size } => {
+                        let mut buffer = decoder.take_lzw_buffer()?;
+                        // `write_lzw_pre_encoded_frame` smuggles `min_code_size` in the first byte.
+                        buffer.push(min_code_size);
+                        State::Lzw { buffer }
+                    }
+                };
+            }
+            State::Pixels { buffer, progress } => {
+                decoder.pixel_converter.read_into_buffer_resumable(
+                    &decoder.current_frame,
+                    buffer,
+                    progress,
+                    decoder.fill_interlaced_blocks,
+                    &mut |out| decoder.decoder.decode_next_bytes(out),
+                )?;
+                let mut frame = decoder.current_frame.take();
+                frame.buffer = Cow::Owned(mem::take(buffer));
+                frame.interlaced = false;
+                self.complete_frame(frame);
+            }
+            State::Lzw { buffer } => {
+                decoder.copy_lzw_data(buffer)?;
+                let mut frame = decoder.current_frame.take();
+                frame.buffer = Cow::Owned(mem::take(buffer));
+                self.complete_frame(frame);
+            }
+            State::Done => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn complete_frame(&mut self, frame: Frame<'static>) {
+        self.frames.push_back(frame);
+        self.frames_decoded += 1;
+        self.state = State::FrameInfo;
+    }
+
+    /// Takes the next frame that has been decoded completely.
+    ///
+    /// Frames are kept until they are taken. Deinterlaces the result.
+    pub fn next_frame(&mut self) -> Option<Frame<'static>> {
+        self.frames.pop_front()
+    }
+
+    /// Returns `true` once the logical screen descriptor and global palette have been decoded.
+    ///
+    /// Until then the image information, such as [`Self::width`], is not available.
+    #[inline]
+    pub fn has_header(&self) -> bool {
+        !matches!(self.state, State::Header)
+    }
+
+    /// Returns `true` once the end of the file has been decoded.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Number of frames that have been decoded completely, including the ones already taken.
+    ///
+    /// This is also the index of the frame being decoded.
+    #[inline]
+    pub fn frames_decoded(&self) -> usize {
+        self.frames_decoded
+    }
+
+    /// Metadata of the frame being decoded.
+    ///
+    /// Returns `None` between frames.
+    pub fn current_frame_info(&self) -> Option<&Frame<'static>> {
+        match self.state {
+            State::Pixels { .. } | State::Lzw { .. } => Some(&self.decoder.current_frame),
+            _ => None,
+        }
+    }
+
+    /// Fraction of the pixels of frame `n` that have been decoded, from 0 to 1.
+    ///
+    /// Frames are counted from 0. LZW data read with [`DecodeOptions::skip_frame_decoding`]
+    /// counts as not decoded until the frame is complete.
+    pub fn frame_progress(&self, n: usize) -> f32 {
+        if n < self.frames_decoded {
+            return 1.0;
+        }
+        match &self.state {
+            State::Pixels { buffer, progress } if n == self.frames_decoded => {
+                if buffer.is_empty() {
+                    return 0.0;
+                }
+                let decoded = progress.decoded_len(self.decoder.line_length());
+                decoded as f32 / buffer.len() as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Pixels of the frame being decoded, in the format of [`Decoder::read_into_buffer`].
+    ///
+    /// Pixels that haven't been decoded yet are 0. With
+    /// [`DecodeOptions::fill_interlaced_blocks`], complete rows of interlaced frames are copied
+    /// over the rows of later passes. Returns `None` between frames.
+    pub fn partial_frame_buffer(&self) -> Option<&[u8]> {
+        match &self.state {
+            State::Pixels { buffer, .. } => Some(buffer),
+            _ => None,
+        }
+    }
+
+    /// Number of complete rows of the frame being decoded.
+    ///
+    /// Rows of interlaced frames are decoded pass by pass, so these are not the top rows.
+    pub fn rows_decoded(&self) -> usize {
+        match &self.state {
+            State::Pixels { progress, .. } => progress.rows_done(),
+            _ => 0,
+        }
+    }
+
+    /// Line length of the current frame
+    #[inline]
+    pub fn line_length(&self) -> usize {
+        self.decoder.line_length()
+    }
+
+    /// Returns the color palette relevant for the frame being decoded
+    #[inline]
+    pub fn palette(&self) -> Result<&[u8], DecodingError> {
+        self.decoder.palette()
+    }
+
+    /// The global color palette
+    #[inline]
+    pub fn global_palette(&self) -> Option<&[u8]> {
+        self.decoder.global_palette()
+    }
+
+    /// Width of the image
+    #[inline]
+    pub fn width(&self) -> u16 {
+        self.decoder.width()
+    }
+
+    /// Height of the image
+    #[inline]
+    pub fn height(&self) -> u16 {
+        self.decoder.height()
+    }
+
+    /// Pixel aspect ratio from the logical screen descriptor.
+    #[inline]
+    pub fn pixel_aspect_ratio(&self) -> PixelAspectRatio {
+        self.decoder.pixel_aspect_ratio()
+    }
+
+    /// Index of the background color in the global palette
+    #[inline]
+    pub fn bg_color(&self) -> Option<usize> {
+        self.decoder.bg_color()
+    }
+
+    /// Number of loop repetitions
+    #[inline]
+    pub fn repeat(&self) -> Repeat {
+        self.decoder.repeat()
+    }
+
+    /// XMP metadata stored in the image.
+    #[inline]
+    pub fn xmp_metadata(&self) -> Option<&[u8]> {
+        self.decoder.xmp_metadata()
+    }
+
+    /// ICC profile stored in the image.
+    #[inline]
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.decoder.icc_profile()
+    }
+
+    /// Comment extensions read so far, see [`Decoder::comments`].
+    #[inline]
+    pub fn comments(&self) -> &[Comment] {
+        self.decoder.comments()
+    }
+}
+
+impl Default for IncrementalDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,113 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use crate::common::{DisposalMethod, Frame};
+
+use super::DecodingError;
+
+/// Position and metadata of the frames in an image, for random access.
+///
+/// Built by [`Decoder::frame_index`](crate::Decoder::frame_index) and used by
+/// [`Decoder::seek_to_frame`](crate::Decoder::seek_to_frame).
+#[derive(Clone, Debug, Default)]
+pub struct FrameIndex {
+    frames: Vec<IndexedFrame>,
+    /// Index of the frame that the canvas state after the last frame depends on, `None` if the
+    /// canvas is in its initial state.
+    canvas_keyframe: Option<usize>,
+}
+
+/// A frame recorded in a [`FrameIndex`].
+#[derive(Clone, Debug)]
+pub struct IndexedFrame {
+    /// Byte offset of the image descriptor, relative to the start of the GIF data.
+    pub offset: u64,
+    /// Metadata of the frame. The palette and buffer are always empty.
+    pub frame: Frame<'static>,
+    /// True if the frame has a local color palette.
+    pub has_local_palette: bool,
+    /// Index of the frame from which composition has to start to render this frame.
+    ///
+    /// Compositing all frames from `keyframe` up to this frame onto a fresh
+    /// [`Screen`](crate::Screen) gives the same result as compositing the whole animation. This
+    /// is the frame itself if it covers the canvas without transparency, or if the canvas was in
+    /// its initial state before it, e.g. after a full-canvas frame was disposed to background.
+    pub keyframe: usize,
+}
+
+impl FrameIndex {
+    /// Number of frames in the image.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// True if the image has no frames.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The recorded frames, in file order.
+    #[inline]
+    #[must_use]
+    pub fn frames(&self) -> &[IndexedFrame] {
+        &self.frames
+    }
+
+    /// The recorded frame at `n`.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, n: usize) -> Option<&IndexedFrame> {
+        self.frames.get(n)
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        offset: u64,
+        frame: &Frame<'static>,
+        screen_width: u16,
+        screen_height: u16,
+    ) -> Result<(), DecodingError> {
+        let n = self.frames.len();
+        let covers_canvas = frame.left == 0
+            && frame.top == 0
+            && frame.width >= screen_width
+            && frame.height >= screen_height;
+        let keyframe = if covers_canvas && frame.transparent.is_none() {
+            n
+        } else {
+            self.canvas_keyframe.unwrap_or(n)
+        };
+        self.canvas_keyframe = match frame.dispose {
+            DisposalMethod::Background if covers_canvas => None,
+            DisposalMethod::Previous => self.canvas_keyframe,
+            _ => Some(keyframe),
+        };
+
+        self.frames
+            .try_reserve(1)
+            .map_err(|_| DecodingError::OutOfMemory)?;
+        self.frames.push(IndexedFrame {
+            offset,
+            frame: Frame {
+                delay: frame.delay,
+                dispose: frame.dispose,
+                transparent: frame.transparent,
+                needs_user_input: frame.needs_user_input,
+                top: frame.top,
+                left: frame.left,
+                width: frame.width,
+                height: frame.height,
+                interlaced: frame.interlaced,
+                palette: None,
+                buffer: Cow::Borrowed(&[]),
+            },
+            has_local_palette: frame.palette.is_some(),
+            keyframe,
+        });
+        Ok(())
+    }
+}
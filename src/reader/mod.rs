@@ -1,24 +1,42 @@
 use alloc::borrow::Cow;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::convert::{TryFrom, TryInto};
+use core::fmt;
 use core::iter::FusedIterator;
 use core::mem;
 use core::num::NonZeroU64;
 
-use crate::common::{Block, Frame};
+use crate::common::{Block, Frame, PlainText};
 use crate::io::{BufRead, BufReader, ReadBuf};
-use crate::{AnyExtension, Extension, Repeat};
+use crate::{AnyExtension, Extension, PixelAspectRatio, Repeat};
 
+#[cfg(feature = "async")]
+mod async_decoder;
 mod converter;
 mod decoder;
+mod font;
+#[cfg(feature = "std")]
+mod incremental;
+#[cfg(feature = "std")]
+mod index;
+mod screen;
 
 pub use self::decoder::{
     Decoded, DecodingError, DecodingFormatError, FrameDataType, FrameDecoder, OutputBuffer,
     StreamingDecoder, Version, PLTE_CHANNELS,
 };
 
+#[cfg(feature = "async")]
+pub use self::async_decoder::AsyncDecoder;
 pub use self::converter::ColorOutput;
 use self::converter::PixelConverter;
+pub(crate) use self::converter::RowOrder;
+#[cfg(feature = "std")]
+pub use self::incremental::IncrementalDecoder;
+#[cfg(feature = "std")]
+pub use self::index::{FrameIndex, IndexedFrame};
+pub use self::screen::Screen;
 
 #[derive(Clone, Debug)]
 /// The maximum amount of memory the decoder is allowed to use for each frame
@@ -62,10 +80,7 @@ impl MemoryLimit {
     fn buffer_size(&self, color: ColorOutput, width: u16, height: u16) -> Option<usize> {
         let pixels = u64::from(width) * u64::from(height);
 
-        let bytes_per_pixel = match color {
-            ColorOutput::Indexed => 1,
-            ColorOutput::RGBA => 4,
-        };
+        let bytes_per_pixel = color.bytes_per_pixel() as u64;
 
         // This cannot overflow because the maximum possible value is 16GiB, well within u64 range
         let total_bytes = pixels * bytes_per_pixel;
@@ -107,6 +122,9 @@ pub struct DecodeOptions {
     skip_frame_decoding: bool,
     check_for_end_code: bool,
     allow_unknown_blocks: bool,
+    retain_app_extensions: bool,
+    fill_interlaced_blocks: bool,
+    app_extension_handlers: AppExtensionHandlers,
 }
 
 impl Default for DecodeOptions {
@@ -127,6 +145,9 @@ impl DecodeOptions {
             skip_frame_decoding: false,
             check_for_end_code: false,
             allow_unknown_blocks: false,
+            retain_app_extensions: false,
+            fill_interlaced_blocks: false,
+            app_extension_handlers: AppExtensionHandlers::default(),
         }
     }
 
@@ -201,6 +222,38 @@ impl DecodeOptions {
         self.allow_unknown_blocks = check;
     }
 
+    /// Configure whether unknown application extensions are kept.
+    ///
+    /// The default is `false`.
+    ///
+    /// When turned on, application extensions other than the ones interpreted by the decoder
+    /// (`NETSCAPE2.0`, `ANIMEXTS1.0`, XMP and ICC) are collected and available from
+    /// [`Decoder::app_extensions`]. Their total size counts against the memory limit.
+    pub fn retain_app_extensions(&mut self, retain: bool) {
+        self.retain_app_extensions = retain;
+    }
+
+    /// Configure whether rows of interlaced frames fill the rows of later passes.
+    ///
+    /// The default is `false`.
+    ///
+    /// When turned on, [`Decoder::read_into_buffer_progressive`] copies each row of an interlaced
+    /// frame downward over the rows that are only decoded in later passes: 8 rows for the first
+    /// pass, 4 for the second and 2 for the third. The buffer then shows a coarse version of the
+    /// whole frame after the first pass, which gets sharper with every pass.
+    pub fn fill_interlaced_blocks(&mut self, fill: bool) {
+        self.fill_interlaced_blocks = fill;
+    }
+
+    /// Add a handler that receives application extensions as they are read.
+    ///
+    /// Each application extension is offered to the handlers in the order they were added, the
+    /// first one accepting it receives its data sub-blocks. This happens independently of the
+    /// decoder's own processing of the extension.
+    pub fn add_app_extension_handler(&mut self, handler: Arc<dyn ApplicationExtensionHandler>) {
+        self.app_extension_handlers.0.push(handler);
+    }
+
     /// Reads the logical screen descriptor including the global color palette
     ///
     /// Returns a [`Decoder`]. All decoder configuration has to be done beforehand.
@@ -213,6 +266,8 @@ struct ReadDecoder<R: ReadBuf> {
     reader: BufReader<R>,
     decoder: StreamingDecoder,
     at_eof: bool,
+    /// Number of bytes consumed since the start of the GIF data.
+    position: u64,
 }
 
 impl<R: ReadBuf> ReadDecoder<R> {
@@ -231,6 +286,7 @@ impl<R: ReadBuf> ReadDecoder<R> {
                 self.decoder.update(buf, write_into)?
             };
             self.reader.consume(consumed);
+            self.position += consumed as u64;
             match result {
                 Decoded::Nothing => (),
                 Decoded::BlockStart(Block::Trailer) => {
@@ -255,21 +311,204 @@ impl<R: ReadBuf> ReadDecoder<R> {
     }
 }
 
+#[cfg(feature = "std")]
+impl<R: ReadBuf + std::io::Seek> ReadDecoder<R> {
+    /// Moves the input to a position relative to the start of the GIF data.
+    fn seek(&mut self, position: u64) -> Result<(), DecodingError> {
+        use std::io::{Seek, SeekFrom};
+
+        let start = self
+            .reader
+            .stream_position()?
+            .checked_sub(self.position)
+            .ok_or_else(|| DecodingError::format("input was moved outside of the decoder"))?;
+        self.reader.seek(SeekFrom::Start(start + position))?;
+        self.position = position;
+        self.at_eof = false;
+        Ok(())
+    }
+}
+
+/// Input that is appended to while decoding, used to drive a [`Decoder`] without blocking.
+///
+/// Reading from it fails with `starved` set when all input has been consumed but more is
+/// expected. The decoder then has to be called again after pushing more input.
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct PushInput {
+    buf: Vec<u8>,
+    pos: usize,
+    eof: bool,
+    starved: bool,
+}
+
+#[cfg(feature = "std")]
+impl PushInput {
+    fn push(&mut self, data: &[u8]) {
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+        }
+        self.buf.extend_from_slice(data);
+        self.starved = false;
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for PushInput {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = &self.buf[self.pos..];
+        if available.is_empty() && !self.eof {
+            self.starved = true;
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        }
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
 /// Headers for supported extensions.
 const EXT_NAME_NETSCAPE: &[u8] = b"NETSCAPE2.0";
+const EXT_NAME_ANIMEXTS: &[u8] = b"ANIMEXTS1.0";
 const EXT_NAME_XMP: &[u8] = b"XMP DataXMP";
 const EXT_NAME_ICC: &[u8] = b"ICCRGBG1012";
 
+/// State when parsing plain text extension
+enum PlainTextState {
+    /// Waiting for the header
+    None,
+    /// Collecting text into the last pending entry
+    Text,
+    /// The header was malformed
+    Skip,
+}
+
 /// State when parsing application extension
 enum AppExtensionState {
     /// Waiting for app name
     None,
+    /// Also used for the equivalent `ANIMEXTS1.0`
     Netscape,
     Xmp,
     Icc,
+    /// Collecting data into the last entry of `app_extensions`
+    Retain,
     Skip,
 }
 
+/// Splits the first sub-block of an application extension into identifier and authentication code.
+fn split_app_identifier(data: &[u8]) -> Option<([u8; 8], [u8; 3])> {
+    let (identifier, authentication_code) = data.split_first_chunk::<8>()?;
+    Some((*identifier, authentication_code.try_into().ok()?))
+}
+
+/// An application extension retained by the [`Decoder`].
+///
+/// See [`DecodeOptions::retain_app_extensions`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApplicationExtension {
+    /// Index of the frame that follows the extension.
+    pub frame_index: usize,
+    /// Application identifier, e.g. `MGK8BIM0`.
+    pub identifier: [u8; 8],
+    /// Application authentication code, e.g. `000`.
+    pub authentication_code: [u8; 3],
+    /// Application data with all sub-blocks concatenated.
+    pub data: Vec<u8>,
+}
+
+/// Receives application extensions while they are decoded.
+///
+/// Register handlers with [`DecodeOptions::add_app_extension_handler`]. The decoder reads one
+/// extension at a time, so an accepted extension is followed by calls to [`Self::sub_block`]
+/// until `is_last` is set, before any other extension is offered.
+///
+/// A handler is shared through an [`Arc`] by the clones of the options and every decoder created
+/// from them, and the caller keeps its own reference to read the results. It therefore only gets
+/// `&self`, state that spans several sub-blocks goes into a `Mutex` or atomics:
+///
+/// ```
+/// use std::sync::{Arc, Mutex};
+///
+/// /// Collects the payload of `MGK8BIM0` extensions.
+/// #[derive(Default)]
+/// struct Photoshop {
+///     data: Mutex<Vec<u8>>,
+/// }
+///
+/// impl gif::ApplicationExtensionHandler for Photoshop {
+///     fn accepts(&self, identifier: &[u8; 8], authentication_code: &[u8; 3]) -> bool {
+///         identifier == b"MGK8BIM0" && authentication_code == b"000"
+///     }
+///
+///     fn sub_block(&self, data: &[u8], _is_last: bool) -> Result<(), gif::DecodingError> {
+///         self.data.lock().unwrap().extend_from_slice(data);
+///         Ok(())
+///     }
+/// }
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let mut encoder = gif::Encoder::new(vec![], 1, 1, &[0, 0, 0])?;
+/// # let app = gif::Extension::Application.into();
+/// # encoder.write_raw_extension(app, &[b"MGK8BIM0000", &[1; 300]])?;
+/// # encoder.write_frame(&gif::Frame::from_indexed_pixels(1, 1, vec![0], None))?;
+/// # let image = encoder.into_inner()?;
+/// let handler = Arc::new(Photoshop::default());
+/// let mut options = gif::DecodeOptions::new();
+/// options.add_app_extension_handler(handler.clone());
+/// let mut decoder = options.read_info(&image[..])?;
+/// while decoder.read_next_frame()?.is_some() {}
+/// assert_eq!(handler.data.lock().unwrap().len(), 300);
+/// # Ok(()) }
+/// ```
+pub trait ApplicationExtensionHandler: Send + Sync {
+    /// Returns whether the handler wants to receive the data of this extension.
+    fn accepts(&self, identifier: &[u8; 8], authentication_code: &[u8; 3]) -> bool;
+
+    /// Receives a data sub-block of an accepted extension.
+    ///
+    /// `is_last` is set for the final sub-block, which is empty if the extension has no data.
+    /// An error aborts decoding.
+    fn sub_block(&self, data: &[u8], is_last: bool) -> Result<(), DecodingError>;
+}
+
+#[derive(Clone, Default)]
+struct AppExtensionHandlers(Vec<Arc<dyn ApplicationExtensionHandler>>);
+
+impl fmt::Debug for AppExtensionHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{} handlers]", self.0.len())
+    }
+}
+
+/// A row of pixels reported by [`Decoder::read_into_buffer_progressive`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodedRow {
+    /// Index of the row in the frame, after deinterlacing.
+    pub y: u16,
+    /// Interlacing pass of the row, from 1 to 4, or `None` if the frame is not interlaced.
+    pub pass: Option<u8>,
+    /// Number of rows starting at `y` that have been written.
+    ///
+    /// This is 1, unless [`DecodeOptions::fill_interlaced_blocks`] is turned on.
+    pub rows: u16,
+}
+
+/// A comment extension read by the [`Decoder`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comment {
+    /// Index of the frame that follows the comment.
+    ///
+    /// Comments after the last frame have an index equal to the number of frames.
+    pub frame_index: usize,
+    /// Comment text with all sub-blocks concatenated.
+    ///
+    /// The specification recommends 7-bit ASCII, but this is not enforced.
+    pub text: Vec<u8>,
+}
+
 #[allow(dead_code)]
 /// GIF decoder. Create [`DecodeOptions`] to get started, and call [`DecodeOptions::read_info`].
 pub struct Decoder<R: ReadBuf> {
@@ -278,13 +517,39 @@ pub struct Decoder<R: ReadBuf> {
     memory_limit: MemoryLimit,
     bg_color: Option<u8>,
     repeat: Repeat,
+    buffer_size_hint: Option<u32>,
     current_frame: Frame<'static>,
     current_frame_data_type: FrameDataType,
     app_extension_state: AppExtensionState,
+    app_extension_handlers: AppExtensionHandlers,
+    /// Handler receiving the application extension being read.
+    active_app_extension_handler: Option<Arc<dyn ApplicationExtensionHandler>>,
+    retain_app_extensions: bool,
+    fill_interlaced_blocks: bool,
+    /// Unknown application extensions, if retained.
+    app_extensions: Vec<ApplicationExtension>,
+    /// Total length of the retained application data, counted against the memory limit.
+    app_extensions_len: usize,
     /// XMP metadata bytes.
     xmp_metadata: Option<Vec<u8>>,
     /// ICC profile bytes.
     icc_profile: Option<Vec<u8>>,
+    /// Comment extensions read so far, in file order.
+    comments: Vec<Comment>,
+    /// Total length of the comment texts, counted against the memory limit.
+    comments_len: usize,
+    /// A comment extension is being read into the last entry of `comments`.
+    reading_comment: bool,
+    /// Number of frames whose metadata has been read.
+    frame_count: usize,
+    plain_text_state: PlainTextState,
+    /// Plain text extensions waiting for the next frame.
+    pending_plain_text: Vec<PlainText>,
+    /// Plain text extensions preceding the current frame.
+    frame_plain_text: Vec<PlainText>,
+    /// Built on demand for seeking.
+    #[cfg(feature = "std")]
+    frame_index: Option<FrameIndex>,
 }
 
 impl<R: ReadBuf> Decoder<R> {
@@ -307,21 +572,42 @@ impl<R: ReadBuf> Decoder<R> {
                 reader: BufReader::new(reader),
                 decoder,
                 at_eof: false,
+                position: 0,
             },
             bg_color: None,
             pixel_converter: PixelConverter::new(options.color_output),
             memory_limit: options.memory_limit.clone(),
             repeat: Repeat::default(),
+            buffer_size_hint: None,
             current_frame: Frame::default(),
             current_frame_data_type: FrameDataType::Pixels,
             app_extension_state: AppExtensionState::None,
+            app_extension_handlers: options.app_extension_handlers.clone(),
+            active_app_extension_handler: None,
+            retain_app_extensions: options.retain_app_extensions,
+            fill_interlaced_blocks: options.fill_interlaced_blocks,
+            app_extensions: Vec::new(),
+            app_extensions_len: 0,
             xmp_metadata: None,
             icc_profile: None,
+            comments: Vec::new(),
+            comments_len: 0,
+            reading_comment: false,
+            frame_count: 0,
+            plain_text_state: PlainTextState::None,
+            pending_plain_text: Vec::new(),
+            frame_plain_text: Vec::new(),
+            #[cfg(feature = "std")]
+            frame_index: None,
         }
     }
 
     fn init(mut self) -> Result<Self, DecodingError> {
-        const APP_EXTENSION: AnyExtension = AnyExtension(Extension::Application as u8);
+        self.read_header()?;
+        Ok(self)
+    }
+
+    fn read_header(&mut self) -> Result<(), DecodingError> {
         loop {
             match self.decoder.decode_next(&mut OutputBuffer::None)? {
                 Some(Decoded::BackgroundColor(bg_color)) => {
@@ -330,11 +616,8 @@ impl<R: ReadBuf> Decoder<R> {
                 Some(Decoded::GlobalPalette(palette)) => {
                     self.pixel_converter.set_global_palette(palette.into());
                 }
-                Some(Decoded::SubBlock {
-                    ext: APP_EXTENSION,
-                    is_last,
-                }) => {
-                    self.read_application_extension(is_last)?;
+                Some(Decoded::SubBlock { ext, is_last }) => {
+                    self.read_extension_sub_block(ext, is_last)?;
                 }
                 Some(Decoded::HeaderEnd) => break,
                 Some(_) => {
@@ -354,7 +637,7 @@ impl<R: ReadBuf> Decoder<R> {
                 self.bg_color = None;
             }
         }
-        Ok(self)
+        Ok(())
     }
 
     fn read_application_extension(&mut self, is_last: bool) -> Result<(), DecodingError> {
@@ -362,8 +645,21 @@ impl<R: ReadBuf> Decoder<R> {
         match self.app_extension_state {
             AppExtensionState::None => {
                 // GIF spec requires len == 11
+                let id = split_app_identifier(data);
+                self.active_app_extension_handler = id.and_then(|(identifier, code)| {
+                    self.app_extension_handlers
+                        .0
+                        .iter()
+                        .find(|handler| handler.accepts(&identifier, &code))
+                        .cloned()
+                });
+                if is_last {
+                    if let Some(handler) = &self.active_app_extension_handler {
+                        handler.sub_block(&[], true)?;
+                    }
+                }
                 self.app_extension_state = match data {
-                    EXT_NAME_NETSCAPE => AppExtensionState::Netscape,
+                    EXT_NAME_NETSCAPE | EXT_NAME_ANIMEXTS => AppExtensionState::Netscape,
                     EXT_NAME_XMP => {
                         self.xmp_metadata = Some(Vec::new());
                         AppExtensionState::Xmp
@@ -372,11 +668,29 @@ impl<R: ReadBuf> Decoder<R> {
                         self.icc_profile = Some(Vec::new());
                         AppExtensionState::Icc
                     }
-                    _ => AppExtensionState::Skip,
+                    _ => match id {
+                        Some((identifier, authentication_code)) if self.retain_app_extensions => {
+                            self.app_extensions
+                                .try_reserve(1)
+                                .map_err(|_| DecodingError::OutOfMemory)?;
+                            self.app_extensions.push(ApplicationExtension {
+                                frame_index: self.frame_count,
+                                identifier,
+                                authentication_code,
+                                data: Vec::new(),
+                            });
+                            AppExtensionState::Retain
+                        }
+                        _ => AppExtensionState::Skip,
+                    },
+                };
+                if is_last {
+                    self.app_extension_state = AppExtensionState::None;
                 }
+                return Ok(());
             }
-            AppExtensionState::Netscape => {
-                if let [1, rest @ ..] = data {
+            AppExtensionState::Netscape => match data {
+                [1, rest @ ..] => {
                     if let Ok(repeat) = rest.try_into().map(u16::from_le_bytes) {
                         self.repeat = if repeat == 0 {
                             Repeat::Infinite
@@ -385,8 +699,13 @@ impl<R: ReadBuf> Decoder<R> {
                         };
                     }
                 }
-                self.app_extension_state = AppExtensionState::Skip;
-            }
+                [2, rest @ ..] => {
+                    if let Ok(size) = rest.try_into().map(u32::from_le_bytes) {
+                        self.buffer_size_hint = Some(size);
+                    }
+                }
+                _ => {}
+            },
             AppExtensionState::Xmp => {
                 if let Some(xmp_metadata) = &mut self.xmp_metadata {
                     // XMP is not written as a valid "pascal-string", so we need to stitch together
@@ -417,10 +736,97 @@ impl<R: ReadBuf> Decoder<R> {
                     icc.extend_from_slice(data);
                 }
             }
+            AppExtensionState::Retain => {
+                self.app_extensions_len = self
+                    .app_extensions_len
+                    .checked_add(data.len())
+                    .ok_or(DecodingError::MemoryLimit)?;
+                self.memory_limit.check_size(self.app_extensions_len)?;
+                if let Some(ext) = self.app_extensions.last_mut() {
+                    self.memory_limit.try_reserve(&mut ext.data, data.len())?;
+                    ext.data.extend_from_slice(data);
+                }
+            }
             AppExtensionState::Skip => {}
         };
+        if let Some(handler) = &self.active_app_extension_handler {
+            handler.sub_block(data, is_last)?;
+        }
         if is_last {
             self.app_extension_state = AppExtensionState::None;
+            self.active_app_extension_handler = None;
+        }
+        Ok(())
+    }
+
+    /// Handles sub-blocks of extensions that may appear anywhere in the file.
+    fn read_extension_sub_block(
+        &mut self,
+        ext: AnyExtension,
+        is_last: bool,
+    ) -> Result<(), DecodingError> {
+        match ext.into_known() {
+            Some(Extension::Application) => self.read_application_extension(is_last),
+            Some(Extension::Comment) => self.read_comment_extension(is_last),
+            Some(Extension::Text) => self.read_plain_text_extension(is_last),
+            _ => Ok(()),
+        }
+    }
+
+    fn read_plain_text_extension(&mut self, is_last: bool) -> Result<(), DecodingError> {
+        let data = self.decoder.decoder.last_ext_sub_block();
+        match self.plain_text_state {
+            PlainTextState::None => {
+                // Malformed headers are ignored, like unknown extensions
+                self.plain_text_state = match PlainText::from_header(data) {
+                    Some(text) => {
+                        self.pending_plain_text
+                            .try_reserve(1)
+                            .map_err(|_| DecodingError::OutOfMemory)?;
+                        self.pending_plain_text.push(text);
+                        PlainTextState::Text
+                    }
+                    None => PlainTextState::Skip,
+                };
+            }
+            PlainTextState::Text => {
+                if let Some(text) = self.pending_plain_text.last_mut() {
+                    self.memory_limit.try_reserve(&mut text.text, data.len())?;
+                    text.text.extend_from_slice(data);
+                }
+            }
+            PlainTextState::Skip => {}
+        }
+        if is_last {
+            self.plain_text_state = PlainTextState::None;
+        }
+        Ok(())
+    }
+
+    fn read_comment_extension(&mut self, is_last: bool) -> Result<(), DecodingError> {
+        let data = self.decoder.decoder.last_ext_sub_block();
+        self.comments_len = self
+            .comments_len
+            .checked_add(data.len())
+            .ok_or(DecodingError::MemoryLimit)?;
+        self.memory_limit.check_size(self.comments_len)?;
+        if !self.reading_comment {
+            self.comments
+                .try_reserve(1)
+                .map_err(|_| DecodingError::OutOfMemory)?;
+            self.comments.push(Comment {
+                frame_index: self.frame_count,
+                text: Vec::new(),
+            });
+            self.reading_comment = true;
+        }
+        if let Some(comment) = self.comments.last_mut() {
+            self.memory_limit
+                .try_reserve(&mut comment.text, data.len())?;
+            comment.text.extend_from_slice(data);
+        }
+        if is_last {
+            self.reading_comment = false;
         }
         Ok(())
     }
@@ -429,7 +835,13 @@ impl<R: ReadBuf> Decoder<R> {
     pub fn next_frame_info(&mut self) -> Result<Option<&Frame<'static>>, DecodingError> {
         loop {
             match self.decoder.decode_next(&mut OutputBuffer::None)? {
+                Some(Decoded::SubBlock { ext, is_last }) => {
+                    self.read_extension_sub_block(ext, is_last)?;
+                }
                 Some(Decoded::FrameMetadata(frame_data_type)) => {
+                    self.frame_count += 1;
+                    self.frame_plain_text.clear();
+                    mem::swap(&mut self.frame_plain_text, &mut self.pending_plain_text);
                     self.current_frame = self.decoder.decoder.current_frame_mut().take();
                     self.current_frame_data_type = frame_data_type;
                     if self.current_frame.palette.is_none() && self.global_palette().is_none() {
@@ -474,22 +886,7 @@ impl<R: ReadBuf> Decoder<R> {
                     )?;
                 }
                 FrameDataType::Lzw { min_code_size } => {
-                    let mut vec = if matches!(self.current_frame.buffer, Cow::Owned(_)) {
-                        let mut vec =
-                            mem::replace(&mut self.current_frame.buffer, Cow::Borrowed(&[]))
-                                .into_owned();
-                        vec.clear();
-                        vec
-                    } else {
-                        Vec::new()
-                    };
-                    // Guesstimate 2bpp
-                    vec.try_reserve(
-                        usize::from(self.current_frame.width)
-                            * usize::from(self.current_frame.height)
-                            / 4,
-                    )
-                    .map_err(|_| DecodingError::OutOfMemory)?;
+                    let mut vec = self.take_lzw_buffer()?;
                     self.copy_lzw_into_buffer(min_code_size, &mut vec)?;
                     self.current_frame.buffer = Cow::Owned(vec);
                 }
@@ -500,6 +897,24 @@ impl<R: ReadBuf> Decoder<R> {
         }
     }
 
+    /// Returns an empty buffer for the LZW data of the current frame, reusing its allocation.
+    fn take_lzw_buffer(&mut self) -> Result<Vec<u8>, DecodingError> {
+        let mut vec = if matches!(self.current_frame.buffer, Cow::Owned(_)) {
+            let mut vec =
+                mem::replace(&mut self.current_frame.buffer, Cow::Borrowed(&[])).into_owned();
+            vec.clear();
+            vec
+        } else {
+            Vec::new()
+        };
+        // Guesstimate 2bpp
+        vec.try_reserve(
+            usize::from(self.current_frame.width) * usize::from(self.current_frame.height) / 4,
+        )
+        .map_err(|_| DecodingError::OutOfMemory)?;
+        Ok(vec)
+    }
+
     /// This is private for iterator's use
     fn take_current_frame(&mut self) -> Option<Frame<'static>> {
         if self.current_frame.buffer.is_empty() {
@@ -520,6 +935,74 @@ impl<R: ReadBuf> Decoder<R> {
             })
     }
 
+    /// Reads the data of the current frame into a pre-allocated buffer, reporting each row.
+    ///
+    /// `on_row` is called after each row has been decoded, with the row and the whole buffer.
+    /// Rows are reported in the order they are stored in the file, so the rows of interlaced
+    /// frames arrive pass by pass. Use this to show a preview of the frame while it is decoded,
+    /// see also [`DecodeOptions::fill_interlaced_blocks`].
+    ///
+    /// `Self::next_frame_info` needs to be called beforehand.
+    /// The length of `buf` must be at least `Self::buffer_size`.
+    /// Deinterlaces the result.
+    pub fn read_into_buffer_progressive(
+        &mut self,
+        buf: &mut [u8],
+        mut on_row: impl FnMut(DecodedRow, &[u8]),
+    ) -> Result<(), DecodingError> {
+        self.pixel_converter.read_into_buffer_progressive(
+            &self.current_frame,
+            buf,
+            self.fill_interlaced_blocks,
+            &mut |out| self.decoder.decode_next_bytes(out),
+            &mut on_row,
+        )
+    }
+
+    /// Reads the data of the current frame into a buffer with padded rows.
+    ///
+    /// Each deinterlaced row of `Self::line_length` bytes is written at `offset + row * stride`,
+    /// the bytes in between are left unchanged. `stride` must be at least `Self::line_length`.
+    /// `Self::next_frame_info` needs to be called beforehand.
+    pub fn read_into_buffer_strided(
+        &mut self,
+        buf: &mut [u8],
+        offset: usize,
+        stride: usize,
+    ) -> Result<(), DecodingError> {
+        self.pixel_converter.read_into_buffer_strided(
+            &self.current_frame,
+            buf,
+            offset,
+            stride,
+            &mut |out| self.decoder.decode_next_bytes(out),
+        )
+    }
+
+    /// Reads the data of the current frame into a larger canvas, at pixel `(x, y)`.
+    ///
+    /// Rows of the canvas are `stride` bytes apart. Use the position of the frame to blit it in
+    /// place onto a canvas the size of the logical screen. All pixels of the frame are written,
+    /// including transparent ones; use [`Screen`] to composite frames instead.
+    /// `Self::next_frame_info` needs to be called beforehand.
+    pub fn read_into_buffer_at(
+        &mut self,
+        buf: &mut [u8],
+        stride: usize,
+        x: usize,
+        y: usize,
+    ) -> Result<(), DecodingError> {
+        let left = x
+            .checked_mul(self.color_output().bytes_per_pixel())
+            .filter(|&left| left.saturating_add(self.line_length()) <= stride)
+            .ok_or_else(|| DecodingError::format("frame does not fit into the canvas"))?;
+        let offset = y
+            .checked_mul(stride)
+            .and_then(|start| start.checked_add(left))
+            .ok_or_else(|| DecodingError::format("buffer too small"))?;
+        self.read_into_buffer_strided(buf, offset, stride)
+    }
+
     fn copy_lzw_into_buffer(
         &mut self,
         min_code_size: u8,
@@ -527,6 +1010,10 @@ impl<R: ReadBuf> Decoder<R> {
     ) -> Result<(), DecodingError> {
         // `write_lzw_pre_encoded_frame` smuggles `min_code_size` in the first byte.
         buf.push(min_code_size);
+        self.copy_lzw_data(buf)
+    }
+
+    fn copy_lzw_data(&mut self, buf: &mut Vec<u8>) -> Result<(), DecodingError> {
         loop {
             match self.decoder.decode_next(&mut OutputBuffer::Vec(buf))? {
                 Some(Decoded::LzwDataCopied(_len)) => {}
@@ -539,7 +1026,8 @@ impl<R: ReadBuf> Decoder<R> {
     /// Reads data of the current frame into a pre-allocated buffer until the buffer has been
     /// filled completely.
     ///
-    /// The buffer length must be an even number of pixels (multiple of 4 if decoding RGBA).
+    /// The buffer length must be an even number of pixels (multiple of
+    /// [`ColorOutput::bytes_per_pixel`]).
     ///
     /// `Self::next_frame_info` needs to be called beforehand. Returns `true` if the supplied
     /// buffer could be filled completely. Should not be called after `false` had been returned.
@@ -562,6 +1050,11 @@ impl<R: ReadBuf> Decoder<R> {
         self.pixel_converter.line_length(&self.current_frame)
     }
 
+    #[inline]
+    pub(crate) fn color_output(&self) -> ColorOutput {
+        self.pixel_converter.color_output()
+    }
+
     /// Returns the color palette relevant for the frame that has been decoded
     #[inline]
     pub fn palette(&self) -> Result<&[u8], DecodingError> {
@@ -590,6 +1083,12 @@ impl<R: ReadBuf> Decoder<R> {
         self.decoder.decoder.height()
     }
 
+    /// Pixel aspect ratio from the logical screen descriptor.
+    #[inline]
+    pub fn pixel_aspect_ratio(&self) -> PixelAspectRatio {
+        self.decoder.decoder.pixel_aspect_ratio()
+    }
+
     /// XMP metadata stored in the image.
     #[inline]
     #[must_use]
@@ -604,6 +1103,47 @@ impl<R: ReadBuf> Decoder<R> {
         self.icc_profile.as_deref()
     }
 
+    /// Unknown application extensions read so far, in file order.
+    ///
+    /// This is empty unless enabled with [`DecodeOptions::retain_app_extensions`]. Like comments,
+    /// extensions are read along with the frame that follows them.
+    #[inline]
+    #[must_use]
+    pub fn app_extensions(&self) -> &[ApplicationExtension] {
+        &self.app_extensions
+    }
+
+    /// All comment extensions read so far, in file order.
+    ///
+    /// Comments are read along with the frame that follows them. Call [`Self::next_frame_info`]
+    /// until it returns `None` to also read comments placed after the last frame.
+    #[inline]
+    #[must_use]
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+
+    /// Comment extensions placed directly before the current frame.
+    #[must_use]
+    pub fn frame_comments(&self) -> &[Comment] {
+        let Some(current) = self.frame_count.checked_sub(1) else {
+            return &[];
+        };
+        let start = self.comments.partition_point(|c| c.frame_index < current);
+        let end = self.comments.partition_point(|c| c.frame_index <= current);
+        &self.comments[start..end]
+    }
+
+    /// Plain text extensions placed directly before the current frame.
+    ///
+    /// Plain text is rarely used and most decoders ignore it. It can be rendered with
+    /// [`Screen::draw_plain_text`].
+    #[inline]
+    #[must_use]
+    pub fn frame_plain_text(&self) -> &[PlainText] {
+        &self.frame_plain_text
+    }
+
     /// Abort decoding and recover the underlying reader wrapped in BufReader
     pub fn into_inner(self) -> BufReader<R> {
         self.decoder.into_inner()
@@ -622,6 +1162,101 @@ impl<R: ReadBuf> Decoder<R> {
     pub fn repeat(&self) -> Repeat {
         self.repeat
     }
+
+    /// Suggested buffer size in bytes for reading the data stream
+    ///
+    /// Read from the buffering sub-block of the `NETSCAPE2.0` extension. It is informational only
+    /// and does not affect decoding.
+    #[inline]
+    pub fn buffer_size_hint(&self) -> Option<u32> {
+        self.buffer_size_hint
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: ReadBuf + std::io::Seek> Decoder<R> {
+    /// Returns the index of all frames in the image.
+    ///
+    /// On first use, the whole image is scanned without decoding the image data. Afterwards the
+    /// decoder is positioned at the start of the frame following the current one, as if by
+    /// [`Self::seek_to_frame`].
+    pub fn frame_index(&mut self) -> Result<&FrameIndex, DecodingError> {
+        if self.frame_index.is_none() {
+            self.seek_to_frame(self.frame_count)?;
+        }
+        Ok(self.frame_index.get_or_insert_with(FrameIndex::default))
+    }
+
+    /// Moves the decoder to the start of frame `n`, counted from zero.
+    ///
+    /// The next call to [`Self::next_frame_info`] or [`Self::read_next_frame`] returns that frame.
+    /// Seeking to the number of frames moves to the end of the image. Extensions placed before
+    /// frame `n` are not read again, apart from its control extension.
+    ///
+    /// To render a frame of an animation, seek to its [`IndexedFrame::keyframe`] and composite
+    /// all frames up to it onto a fresh [`Screen`].
+    pub fn seek_to_frame(&mut self, n: usize) -> Result<(), DecodingError> {
+        if self.frame_index.is_none() {
+            self.frame_index = Some(self.scan_frames()?);
+        }
+        let frames = self
+            .frame_index
+            .as_ref()
+            .map_or(&[][..], |index| index.frames());
+        if n > frames.len() {
+            return Err(DecodingError::format("frame index out of range"));
+        }
+        match frames.get(n) {
+            Some(entry) => {
+                let frame = entry.frame.clone();
+                self.decoder.seek(entry.offset + 1)?;
+                self.decoder.decoder.seek_to_image(frame);
+            }
+            None => self.decoder.at_eof = true,
+        }
+
+        self.frame_count = n;
+        self.current_frame = Frame::default();
+        self.app_extension_state = AppExtensionState::None;
+        self.active_app_extension_handler = None;
+        self.reading_comment = false;
+        self.plain_text_state = PlainTextState::None;
+        self.pending_plain_text.clear();
+        self.frame_plain_text.clear();
+        // Extensions after frame `n` are read again
+        self.comments
+            .truncate(self.comments.partition_point(|c| c.frame_index <= n));
+        self.comments_len = self.comments.iter().map(|c| c.text.len()).sum();
+        self.app_extensions
+            .truncate(self.app_extensions.partition_point(|a| a.frame_index <= n));
+        self.app_extensions_len = self.app_extensions.iter().map(|a| a.data.len()).sum();
+        Ok(())
+    }
+
+    fn scan_frames(&mut self) -> Result<FrameIndex, DecodingError> {
+        self.decoder.seek(0)?;
+        self.decoder.decoder.rewind();
+        let mut index = FrameIndex::default();
+        let mut offset = 0;
+        loop {
+            match self.decoder.decode_next(&mut OutputBuffer::None)? {
+                Some(Decoded::BlockStart(Block::Image)) => {
+                    // The block introducer has been consumed
+                    offset = self.decoder.position - 1;
+                }
+                Some(Decoded::FrameMetadata(_)) => {
+                    index.push(
+                        offset,
+                        self.decoder.decoder.current_frame(),
+                        self.width(),
+                        self.height(),
+                    )?;
+                }
+                Some(_) => {}
+                None => return Ok(index),
+            }
+        }
+    }
 }
 
 impl<R: ReadBuf> IntoIterator for Decoder<R> {
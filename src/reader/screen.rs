@@ -0,0 +1,390 @@
+use alloc::vec::Vec;
+
+use super::converter::{ColorOutput, N_CHANNELS};
+use super::decoder::{DecodingError, PLTE_CHANNELS};
+use super::font::{glyph, GLYPH_SIZE};
+use super::Decoder;
+use crate::common::{DisposalMethod, Frame, PlainText};
+use crate::io::ReadBuf;
+
+/// A canvas area touched by a frame, clipped to the screen.
+#[derive(Clone, Copy, Debug, Default)]
+struct Rect {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+/// Full-size canvas that composites frames according to their disposal method.
+///
+/// The [`Decoder`] only returns the sub-rectangle of the canvas that each frame updates. `Screen`
+/// keeps the state of the whole logical screen between frames: it applies the disposal method of
+/// the previous frame, skips transparent pixels and resolves the local or global palette.
+///
+/// Frames decoded with any [`ColorOutput`] are supported, the canvas is always RGBA. In the
+/// indexed mode an indexed canvas is maintained in addition to the RGBA canvas. Its indices
+/// refer to the palette of the frame that wrote them, so it is only meaningful on its own if all
+/// frames share the global palette. [`ColorOutput::RGB`] and [`ColorOutput::Luma`] frames carry no
+/// transparency, so all of their pixels are drawn.
+///
+/// ```no_run
+/// # fn main() -> Result<(), gif::DecodingError> {
+/// let file = std::fs::File::open("tests/samples/moon_impact.gif")?;
+/// let mut decoder = gif::DecodeOptions::new().read_info(file)?;
+/// let mut screen = gif::Screen::new_decoder(&decoder);
+/// while let Some(frame) = decoder.read_next_frame()? {
+///     screen.blit_frame(frame)?;
+///     let _rgba: &[u8] = screen.pixels_rgba();
+/// }
+/// # Ok(()) }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Screen {
+    width: u16,
+    height: u16,
+    color_output: ColorOutput,
+    global_palette: Option<Vec<u8>>,
+    bg_color: Option<u8>,
+    rgba: Vec<u8>,
+    indexed: Option<Vec<u8>>,
+    /// Disposal of the last blitted frame, applied before the next one.
+    pending_disposal: Option<(DisposalMethod, Rect)>,
+    /// Canvas area saved for `DisposalMethod::Previous`.
+    saved_rgba: Vec<u8>,
+    saved_indexed: Vec<u8>,
+}
+
+impl Screen {
+    /// Creates a screen matching the logical screen of the decoder.
+    ///
+    /// The color output and global palette are taken from the decoder. Like most browsers, the
+    /// background is transparent. For the opaque background color of the specification, pass
+    /// [`Decoder::bg_color`] to [`Screen::set_bg_color`].
+    #[must_use]
+    pub fn new_decoder<R: ReadBuf>(decoder: &Decoder<R>) -> Self {
+        Self::new(
+            decoder.width(),
+            decoder.height(),
+            decoder.color_output(),
+            decoder.global_palette(),
+        )
+    }
+
+    /// Creates a screen of the given size.
+    ///
+    /// `color_output` must match the setting used to decode the frames that are blitted onto it.
+    /// The canvas starts out fully transparent; use [`Screen::set_bg_color`] to fill it with a
+    /// background color instead.
+    #[must_use]
+    pub fn new(
+        width: u16,
+        height: u16,
+        color_output: ColorOutput,
+        global_palette: Option<&[u8]>,
+    ) -> Self {
+        let pixels = usize::from(width) * usize::from(height);
+        Self {
+            width,
+            height,
+            color_output,
+            global_palette: global_palette.map(<[u8]>::to_vec),
+            bg_color: None,
+            rgba: vec![0; pixels * N_CHANNELS],
+            indexed: match color_output {
+                ColorOutput::Indexed => Some(vec![0; pixels]),
+                _ => None,
+            },
+            pending_disposal: None,
+            saved_rgba: Vec::new(),
+            saved_indexed: Vec::new(),
+        }
+    }
+
+    /// Sets the index of the background color in the global palette.
+    ///
+    /// The background is used for the initial canvas and by [`DisposalMethod::Background`]. It
+    /// is opaque if the index refers to an entry of the global palette, and fully transparent
+    /// when `None`, the default.
+    ///
+    /// This resets the canvas to the background.
+    pub fn set_bg_color(&mut self, bg_color: Option<usize>) {
+        self.bg_color = bg_color.and_then(|bg| u8::try_from(bg).ok());
+        self.pending_disposal = None;
+        let full = Rect {
+            left: 0,
+            top: 0,
+            width: usize::from(self.width),
+            height: usize::from(self.height),
+        };
+        self.fill_background(full);
+    }
+
+    /// Width of the canvas.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Height of the canvas.
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// The composited canvas as RGBA pixels, `width * height * 4` bytes.
+    #[inline]
+    #[must_use]
+    pub fn pixels_rgba(&self) -> &[u8] {
+        &self.rgba
+    }
+
+    /// The composited canvas as palette indices, `width * height` bytes.
+    ///
+    /// Only available if the screen was created with [`ColorOutput::Indexed`].
+    #[inline]
+    #[must_use]
+    pub fn pixels_indexed(&self) -> Option<&[u8]> {
+        self.indexed.as_deref()
+    }
+
+    /// Disposes the previous frame and draws the next one onto the canvas.
+    ///
+    /// The frame must have been decoded into pixels with the color output of this screen. Parts of
+    /// the frame outside of the logical screen are ignored.
+    pub fn blit_frame(&mut self, frame: &Frame<'_>) -> Result<(), DecodingError> {
+        let bytes_per_pixel = self.color_output.bytes_per_pixel();
+        let frame_width = usize::from(frame.width);
+        if frame.buffer.len() < frame_width * usize::from(frame.height) * bytes_per_pixel {
+            return Err(DecodingError::format("frame buffer too small"));
+        }
+
+        if let Some((disposal, rect)) = self.pending_disposal.take() {
+            self.dispose(disposal, rect);
+        }
+
+        let rect = self.clip(frame);
+        if frame.dispose == DisposalMethod::Previous {
+            self.save(rect);
+        }
+
+        let palette = frame
+            .palette
+            .as_deref()
+            .or(self.global_palette.as_deref())
+            .unwrap_or_default();
+        let screen_width = usize::from(self.width);
+        for y in 0..rect.height {
+            let src_start = (y + rect.top - usize::from(frame.top)) * frame_width
+                + (rect.left - usize::from(frame.left));
+            let dst_start = (y + rect.top) * screen_width + rect.left;
+            for x in 0..rect.width {
+                let src = src_start + x;
+                let dst = dst_start + x;
+                let rgba = &mut self.rgba[dst * N_CHANNELS..][..N_CHANNELS];
+                match self.color_output {
+                    ColorOutput::Indexed => {
+                        let idx = frame.buffer[src];
+                        if frame.transparent == Some(idx) {
+                            continue;
+                        }
+                        let plte_offset = PLTE_CHANNELS * usize::from(idx);
+                        if let Some(color) = palette.get(plte_offset..plte_offset + PLTE_CHANNELS) {
+                            rgba[..PLTE_CHANNELS].copy_from_slice(color);
+                            rgba[3] = 0xFF;
+                            if let Some(indexed) = &mut self.indexed {
+                                indexed[dst] = idx;
+                            }
+                        }
+                    }
+                    color_output => {
+                        let pixel = &frame.buffer[src * bytes_per_pixel..][..bytes_per_pixel];
+                        if let Some(pixel) = to_rgba(color_output, pixel) {
+                            rgba.copy_from_slice(&pixel);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.pending_disposal = Some((frame.dispose, rect));
+        Ok(())
+    }
+
+    /// Renders a plain text extension onto the canvas with a built-in 8x8 pixel font.
+    ///
+    /// Glyphs are scaled to the cell size. Cells of the text grid are filled with the background
+    /// color first, and characters that do not fit into the grid are ignored. Colors are taken
+    /// from the global palette.
+    ///
+    /// The text is drawn onto the current canvas and is not subject to disposal. Call this after
+    /// blitting the frame that followed the extension in the file.
+    pub fn draw_plain_text(&mut self, text: &PlainText) {
+        let (cell_width, cell_height) =
+            (usize::from(text.cell_width), usize::from(text.cell_height));
+        if cell_width == 0 || cell_height == 0 {
+            return;
+        }
+        let columns = usize::from(text.width) / cell_width;
+        let rows = usize::from(text.height) / cell_height;
+        let Some(palette) = self.global_palette.as_deref() else {
+            return;
+        };
+        let color = |index: u8| {
+            let plte_offset = PLTE_CHANNELS * usize::from(index);
+            palette
+                .get(plte_offset..plte_offset + PLTE_CHANNELS)
+                .map(|c| [c[0], c[1], c[2], 0xFF])
+        };
+        let foreground = color(text.foreground);
+        let background = color(text.background);
+
+        let screen_width = usize::from(self.width);
+        let screen_height = usize::from(self.height);
+        for row in 0..rows {
+            for column in 0..columns {
+                let c = text
+                    .text
+                    .get(row * columns + column)
+                    .copied()
+                    .unwrap_or(b' ');
+                let glyph = glyph(c);
+                let cell_left = usize::from(text.left) + column * cell_width;
+                let cell_top = usize::from(text.top) + row * cell_height;
+                for cy in 0..cell_height {
+                    let y = cell_top + cy;
+                    if y >= screen_height {
+                        break;
+                    }
+                    let glyph_row = glyph[cy * GLYPH_SIZE / cell_height];
+                    for cx in 0..cell_width {
+                        let x = cell_left + cx;
+                        if x >= screen_width {
+                            break;
+                        }
+                        let set = glyph_row & (1 << (cx * GLYPH_SIZE / cell_width)) != 0;
+                        let (rgba, index) = if set {
+                            (foreground, text.foreground)
+                        } else {
+                            (background, text.background)
+                        };
+                        let Some(rgba) = rgba else { continue };
+                        let dst = y * screen_width + x;
+                        self.rgba[dst * N_CHANNELS..][..N_CHANNELS].copy_from_slice(&rgba);
+                        if let Some(indexed) = &mut self.indexed {
+                            indexed[dst] = index;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Intersects the frame with the logical screen.
+    fn clip(&self, frame: &Frame<'_>) -> Rect {
+        let left = usize::from(frame.left).min(usize::from(self.width));
+        let top = usize::from(frame.top).min(usize::from(self.height));
+        let right =
+            (usize::from(frame.left) + usize::from(frame.width)).min(usize::from(self.width));
+        let bottom =
+            (usize::from(frame.top) + usize::from(frame.height)).min(usize::from(self.height));
+        if right <= left || bottom <= top {
+            return Rect::default();
+        }
+        Rect {
+            left,
+            top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+
+    fn dispose(&mut self, disposal: DisposalMethod, rect: Rect) {
+        match disposal {
+            DisposalMethod::Any | DisposalMethod::Keep => {}
+            DisposalMethod::Background => self.fill_background(rect),
+            DisposalMethod::Previous => self.restore(rect),
+        }
+    }
+
+    fn background(&self) -> ([u8; N_CHANNELS], u8) {
+        let Some(bg) = self.bg_color else {
+            return ([0; N_CHANNELS], 0);
+        };
+        let plte_offset = PLTE_CHANNELS * usize::from(bg);
+        match self
+            .global_palette
+            .as_deref()
+            .and_then(|palette| palette.get(plte_offset..plte_offset + PLTE_CHANNELS))
+        {
+            Some(color) => ([color[0], color[1], color[2], 0xFF], bg),
+            None => ([0; N_CHANNELS], bg),
+        }
+    }
+
+    fn fill_background(&mut self, rect: Rect) {
+        let (rgba, index) = self.background();
+        let screen_width = usize::from(self.width);
+        for y in rect.top..rect.top + rect.height {
+            let start = y * screen_width + rect.left;
+            let end = start + rect.width;
+            for pixel in
+                self.rgba[start * N_CHANNELS..end * N_CHANNELS].chunks_exact_mut(N_CHANNELS)
+            {
+                pixel.copy_from_slice(&rgba);
+            }
+            if let Some(indexed) = &mut self.indexed {
+                indexed[start..end].fill(index);
+            }
+        }
+    }
+
+    fn save(&mut self, rect: Rect) {
+        let screen_width = usize::from(self.width);
+        self.saved_rgba.clear();
+        self.saved_indexed.clear();
+        for y in rect.top..rect.top + rect.height {
+            let start = y * screen_width + rect.left;
+            let end = start + rect.width;
+            self.saved_rgba
+                .extend_from_slice(&self.rgba[start * N_CHANNELS..end * N_CHANNELS]);
+            if let Some(indexed) = &self.indexed {
+                self.saved_indexed.extend_from_slice(&indexed[start..end]);
+            }
+        }
+    }
+
+    fn restore(&mut self, rect: Rect) {
+        let screen_width = usize::from(self.width);
+        for (row, y) in (rect.top..rect.top + rect.height).enumerate() {
+            let start = y * screen_width + rect.left;
+            let end = start + rect.width;
+            let saved = row * rect.width;
+            self.rgba[start * N_CHANNELS..end * N_CHANNELS].copy_from_slice(
+                &self.saved_rgba[saved * N_CHANNELS..(saved + rect.width) * N_CHANNELS],
+            );
+            if let Some(indexed) = &mut self.indexed {
+                indexed[start..end].copy_from_slice(&self.saved_indexed[saved..saved + rect.width]);
+            }
+        }
+    }
+}
+
+/// Converts a decoded pixel to RGBA, `None` if it is transparent.
+#[inline]
+fn to_rgba(color_output: ColorOutput, pixel: &[u8]) -> Option<[u8; N_CHANNELS]> {
+    let rgba = match color_output {
+        // Alpha is either 0 or 0xFF, so premultiplied pixels are unchanged
+        ColorOutput::RGBA | ColorOutput::PremultipliedRGBA => {
+            [pixel[0], pixel[1], pixel[2], pixel[3]]
+        }
+        ColorOutput::BGRA => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        ColorOutput::RGB => [pixel[0], pixel[1], pixel[2], 0xFF],
+        ColorOutput::Luma => [pixel[0], pixel[0], pixel[0], 0xFF],
+        ColorOutput::LumaAlpha => [pixel[0], pixel[0], pixel[0], pixel[1]],
+        ColorOutput::Indexed => return None,
+    };
+    (rgba[3] != 0).then_some(rgba)
+}
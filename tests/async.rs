@@ -0,0 +1,318 @@
+#![cfg(feature = "async")]
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::executor::block_on;
+use futures::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use futures::FutureExt;
+use gif::{AsyncDecoder, AsyncEncoder, ColorOutput, DecodeOptions, Encoder, Frame, Repeat};
+
+const SAMPLES: [&[u8]; 4] = [
+    include_bytes!("samples/sample_1.gif"),
+    include_bytes!("samples/interlaced.gif"),
+    include_bytes!("samples/moon_impact.gif"),
+    include_bytes!("samples/beacon_xmp.gif"),
+];
+
+/// Returns a few bytes at a time, and is not ready on every other poll.
+struct SlowReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+    ready: bool,
+}
+
+impl<'a> SlowReader<'a> {
+    fn new(data: &'a [u8], chunk: usize) -> Self {
+        Self {
+            data,
+            chunk,
+            ready: false,
+        }
+    }
+}
+
+impl AsyncRead for SlowReader<'_> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let data = futures::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.consume(len);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncBufRead for SlowReader<'_> {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        this.ready = !this.ready;
+        if !this.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let len = this.chunk.min(this.data.len());
+        Poll::Ready(Ok(&this.data[..len]))
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        self.data = &self.data[amt..];
+    }
+}
+
+/// Position, size and delay, transparent index, palette and pixels.
+type FrameData = ([u16; 5], Option<u8>, Option<Vec<u8>>, Vec<u8>);
+
+fn frame_data(frame: &Frame<'_>) -> FrameData {
+    (
+        [
+            frame.left,
+            frame.top,
+            frame.width,
+            frame.height,
+            frame.delay,
+        ],
+        frame.transparent,
+        frame.palette.clone(),
+        frame.buffer.to_vec(),
+    )
+}
+
+fn decode_sync(image: &[u8], options: DecodeOptions) -> Vec<FrameData> {
+    let mut decoder = options.read_info(image).unwrap();
+    let mut frames = vec![];
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push(frame_data(frame));
+    }
+    frames
+}
+
+fn decode_async(image: &[u8], chunk: usize, options: DecodeOptions) -> Vec<FrameData> {
+    block_on(async {
+        let reader = SlowReader::new(image, chunk);
+        let mut decoder = options.read_info_async(reader).await.unwrap();
+        let mut frames = vec![];
+        while let Some(frame) = decoder.read_next_frame().await.unwrap() {
+            frames.push(frame_data(frame));
+        }
+        frames
+    })
+}
+
+#[test]
+fn matches_sync_decoder() {
+    for image in SAMPLES {
+        for color in [
+            ColorOutput::Indexed,
+            ColorOutput::RGBA,
+            ColorOutput::LumaAlpha,
+        ] {
+            let mut options = DecodeOptions::new();
+            options.set_color_output(color);
+            let expected = decode_sync(image, options.clone());
+            for chunk in [1, 7, 4096] {
+                assert_eq!(decode_async(image, chunk, options.clone()), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn lzw_data() {
+    let mut options = DecodeOptions::new();
+    options.skip_frame_decoding(true);
+    for image in SAMPLES {
+        let expected = decode_sync(image, options.clone());
+        assert_eq!(decode_async(image, 3, options.clone()), expected);
+    }
+}
+
+#[test]
+fn read_into_buffer() {
+    let image = include_bytes!("samples/interlaced.gif");
+    let expected = decode_sync(image, DecodeOptions::new());
+    block_on(async {
+        let mut decoder = AsyncDecoder::new(SlowReader::new(image, 5)).await.unwrap();
+        assert_eq!(decoder.width(), expected[0].0[2]);
+        let mut n = 0;
+        while let Some(frame) = decoder.next_frame_info().await.unwrap() {
+            assert!(frame.interlaced);
+            let mut buffer = vec![0; decoder.buffer_size()];
+            decoder.read_into_buffer(&mut buffer).await.unwrap();
+            assert_eq!(buffer, expected[n].3);
+            n += 1;
+        }
+        assert_eq!(n, expected.len());
+    });
+}
+
+#[test]
+fn metadata() {
+    let image = include_bytes!("samples/beacon_xmp.gif");
+    let sync = DecodeOptions::new().read_info(&image[..]).unwrap();
+    block_on(async {
+        let decoder = AsyncDecoder::new(SlowReader::new(image, 2)).await.unwrap();
+        assert_eq!(decoder.xmp_metadata(), sync.xmp_metadata());
+        assert_eq!(decoder.global_palette(), sync.global_palette());
+        assert_eq!(decoder.repeat(), sync.repeat());
+    });
+}
+
+#[test]
+fn truncated() {
+    let image = include_bytes!("samples/moon_impact.gif");
+    for len in [0, 10, 800, image.len() / 2, image.len() - 1] {
+        let data = &image[..len];
+        let sync = (|| {
+            let mut decoder = DecodeOptions::new().read_info(data)?;
+            while decoder.read_next_frame()?.is_some() {}
+            Ok::<_, gif::DecodingError>(())
+        })();
+        let result = block_on(async {
+            let mut decoder = AsyncDecoder::new(SlowReader::new(data, 9)).await?;
+            while decoder.read_next_frame().await?.is_some() {}
+            Ok::<_, gif::DecodingError>(())
+        });
+        assert_eq!(result.is_err(), sync.is_err(), "{len}");
+        // Only a missing trailer is accepted
+        if len < image.len() - 1 {
+            assert!(result.is_err(), "{len}");
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn tokio_writer() {
+    use gif::io::TokioCompat;
+
+    let frames = frames();
+    let writer = block_on(async {
+        let mut encoder = AsyncEncoder::new(TokioCompat::new(vec![]), 8, 8, &PALETTE).unwrap();
+        encoder.set_repeat(Repeat::Infinite).await.unwrap();
+        encoder.write_comment(b"async").await.unwrap();
+        for frame in &frames {
+            encoder.write_frame(frame).await.unwrap();
+        }
+        encoder.finish().await.unwrap()
+    });
+    assert_eq!(writer.into_inner(), encode_sync(&frames));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn tokio_reader() {
+    use gif::io::TokioCompat;
+
+    let image = include_bytes!("samples/moon_impact.gif");
+    let expected = decode_sync(image, DecodeOptions::new());
+    block_on(async {
+        let mut decoder = AsyncDecoder::new(TokioCompat::new(&image[..]))
+            .await
+            .unwrap();
+        let mut frames = vec![];
+        while let Some(frame) = decoder.read_next_frame().await.unwrap() {
+            frames.push(frame_data(frame));
+        }
+        assert_eq!(frames, expected);
+    });
+}
+
+/// Accepts a few bytes at a time, and is not ready on every other poll.
+#[derive(Default)]
+struct SlowWriter {
+    data: Vec<u8>,
+    ready: bool,
+    flushed: bool,
+}
+
+impl AsyncWrite for SlowWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.ready = !this.ready;
+        if !this.ready {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        let len = buf.len().min(100);
+        this.data.extend_from_slice(&buf[..len]);
+        this.flushed = false;
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().flushed = true;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+const PALETTE: [u8; 6] = [0, 0, 0, 0xFF, 0xFF, 0xFF];
+
+fn frames() -> Vec<Frame<'static>> {
+    (0..3u8)
+        .map(|i| {
+            let pixels: Vec<u8> = (0..64).map(|p| (p / 8 + i) % 2).collect();
+            let mut frame = Frame::from_indexed_pixels(8, 8, pixels, None);
+            frame.delay = 10;
+            frame
+        })
+        .collect()
+}
+
+fn encode_sync(frames: &[Frame<'_>]) -> Vec<u8> {
+    let mut encoder = Encoder::new(vec![], 8, 8, &PALETTE).unwrap();
+    encoder.set_repeat(Repeat::Infinite).unwrap();
+    encoder.write_comment(b"async").unwrap();
+    for frame in frames {
+        encoder.write_frame(frame).unwrap();
+    }
+    encoder.into_inner().unwrap()
+}
+
+#[test]
+fn encoder_matches_sync_encoder() {
+    let frames = frames();
+    let writer = block_on(async {
+        let mut encoder = AsyncEncoder::new(SlowWriter::default(), 8, 8, &PALETTE).unwrap();
+        encoder.set_repeat(Repeat::Infinite).await.unwrap();
+        encoder.write_comment(b"async").await.unwrap();
+        for frame in &frames {
+            encoder.write_frame(frame).await.unwrap();
+        }
+        encoder.finish().await.unwrap()
+    });
+    assert!(writer.flushed);
+    assert_eq!(writer.data, encode_sync(&frames));
+    assert_eq!(decode_sync(&writer.data, DecodeOptions::new()).len(), 3);
+}
+
+#[test]
+fn encoder_resumes_dropped_write() {
+    let frames = frames();
+    let mut encoder = AsyncEncoder::new(SlowWriter::default(), 8, 8, &PALETTE).unwrap();
+    block_on(async {
+        encoder.set_repeat(Repeat::Infinite).await.unwrap();
+        encoder.write_comment(b"async").await.unwrap();
+    });
+    // The writer is not ready on the first poll, so the future is dropped without writing
+    assert!(encoder.write_frame(&frames[0]).now_or_never().is_none());
+    let writer = block_on(async {
+        for frame in &frames[1..] {
+            encoder.write_frame(frame).await.unwrap();
+        }
+        encoder.finish().await.unwrap()
+    });
+    assert_eq!(writer.data, encode_sync(&frames));
+}
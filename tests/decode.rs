@@ -296,6 +296,7 @@ fn check_last_extension_returns() {
     let mut expect_label = true;
     let mut expect_xmp = false;
     let mut xmp_len = 0;
+    let mut seen_aspect_ratio = false;
     loop {
         let (consumed, result) = {
             if buf.is_empty() {
@@ -321,10 +322,507 @@ fn check_last_extension_returns() {
                     expect_label = true;
                 }
             }
+            Decoded::PixelAspectRatio(ratio) => {
+                assert_eq!(ratio, gif::PixelAspectRatio::UNSPECIFIED);
+                seen_aspect_ratio = true;
+            }
             Decoded::HeaderEnd => break,
             _ => {}
         }
     }
 
+    assert!(seen_aspect_ratio);
     assert_eq!(xmp_len, EXPECTED_METADATA.len() + 257);
 }
+
+fn create_image_with_comments() -> Vec<u8> {
+    let mut encoder = Encoder::new(vec![], 1, 1, &[0, 0, 0]).unwrap();
+    let frame = Frame::from_indexed_pixels(1, 1, vec![0], None);
+    encoder.write_comment(b"before first").unwrap();
+    encoder.write_comment(&[b'x'; 600]).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_comment(b"").unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_comment(b"after last").unwrap();
+    encoder.into_inner().unwrap()
+}
+
+#[test]
+fn comments() {
+    let image = create_image_with_comments();
+    let mut decoder = DecodeOptions::new().read_info(&image[..]).unwrap();
+    assert!(decoder.frame_comments().is_empty());
+
+    decoder.read_next_frame().unwrap().unwrap();
+    let comments = decoder.frame_comments();
+    assert_eq!(comments.len(), 2);
+    assert_eq!(comments[0].text, b"before first");
+    assert_eq!(comments[1].text, [b'x'; 600]);
+
+    decoder.read_next_frame().unwrap().unwrap();
+    assert!(decoder.frame_comments().is_empty());
+
+    decoder.read_next_frame().unwrap().unwrap();
+    let comments = decoder.frame_comments();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].frame_index, 2);
+    assert!(comments[0].text.is_empty());
+
+    assert!(decoder.read_next_frame().unwrap().is_none());
+    let comments = decoder.comments();
+    assert_eq!(comments.len(), 4);
+    assert_eq!(
+        comments.iter().map(|c| c.frame_index).collect::<Vec<_>>(),
+        [0, 0, 2, 3]
+    );
+    assert_eq!(comments[3].text, b"after last");
+}
+
+#[test]
+fn comments_memory_limit() {
+    let image = create_image_with_comments();
+    let mut options = DecodeOptions::new();
+    options.set_memory_limit(gif::MemoryLimit::Bytes(500.try_into().unwrap()));
+    assert!(matches!(
+        options.read_info(&image[..]),
+        Err(gif::DecodingError::MemoryLimit)
+    ));
+}
+
+#[test]
+fn plain_text() {
+    let text = gif::PlainText {
+        left: 1,
+        top: 2,
+        width: 48,
+        height: 16,
+        cell_width: 8,
+        cell_height: 16,
+        foreground: 1,
+        background: 0,
+        text: b"Hello!".repeat(60),
+    };
+    let mut encoder = Encoder::new(vec![], 50, 20, &[0, 0, 0, 0xFF, 0xFF, 0xFF]).unwrap();
+    let frame = Frame::from_indexed_pixels(1, 1, vec![0], None);
+    encoder
+        .write_extension(gif::ExtensionData::PlainText(text.clone()))
+        .unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    // A malformed header is skipped
+    encoder
+        .write_raw_extension(gif::Extension::Text.into(), &[&[1, 2, 3], b"text"])
+        .unwrap();
+    encoder.write_frame(&frame).unwrap();
+    let image = encoder.into_inner().unwrap();
+
+    let mut decoder = DecodeOptions::new().read_info(&image[..]).unwrap();
+    decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(decoder.frame_plain_text(), [text]);
+    decoder.read_next_frame().unwrap().unwrap();
+    assert!(decoder.frame_plain_text().is_empty());
+    decoder.read_next_frame().unwrap().unwrap();
+    assert!(decoder.frame_plain_text().is_empty());
+}
+
+fn create_image_with_app_extensions() -> Vec<u8> {
+    let app = gif::Extension::Application.into();
+    let mut encoder = Encoder::new(vec![], 1, 1, &[0, 0, 0]).unwrap();
+    let frame = Frame::from_indexed_pixels(1, 1, vec![0], None);
+    encoder.set_repeat(Repeat::Finite(3)).unwrap();
+    encoder
+        .write_raw_extension(app, &[b"MGK8BIM0000", &[b'8'; 300]])
+        .unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_raw_extension(app, &[b"MGKIPTC0000"]).unwrap();
+    // Malformed identifiers are skipped
+    encoder
+        .write_raw_extension(app, &[b"short", b"data"])
+        .unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.into_inner().unwrap()
+}
+
+#[test]
+fn retain_app_extensions() {
+    let image = create_image_with_app_extensions();
+    let decoder = DecodeOptions::new().read_info(&image[..]).unwrap();
+    assert!(decoder.app_extensions().is_empty());
+
+    let mut options = DecodeOptions::new();
+    options.retain_app_extensions(true);
+    let mut decoder = options.read_info(&image[..]).unwrap();
+    assert_eq!(decoder.repeat(), Repeat::Finite(3));
+    while decoder.read_next_frame().unwrap().is_some() {}
+    assert_eq!(
+        decoder.app_extensions(),
+        [
+            gif::ApplicationExtension {
+                frame_index: 0,
+                identifier: *b"MGK8BIM0",
+                authentication_code: *b"000",
+                data: vec![b'8'; 300],
+            },
+            gif::ApplicationExtension {
+                frame_index: 1,
+                identifier: *b"MGKIPTC0",
+                authentication_code: *b"000",
+                data: vec![],
+            },
+        ]
+    );
+}
+
+#[derive(Default)]
+struct IptcHandler {
+    sub_blocks: std::sync::Mutex<Vec<(Vec<u8>, bool)>>,
+}
+
+impl gif::ApplicationExtensionHandler for IptcHandler {
+    fn accepts(&self, identifier: &[u8; 8], authentication_code: &[u8; 3]) -> bool {
+        identifier.starts_with(b"MGK") && authentication_code == b"000"
+    }
+
+    fn sub_block(&self, data: &[u8], is_last: bool) -> Result<(), gif::DecodingError> {
+        self.sub_blocks
+            .lock()
+            .unwrap()
+            .push((data.to_vec(), is_last));
+        Ok(())
+    }
+}
+
+#[test]
+fn app_extension_handler() {
+    let image = create_image_with_app_extensions();
+    let handler = std::sync::Arc::new(IptcHandler::default());
+    let mut options = DecodeOptions::new();
+    options.add_app_extension_handler(handler.clone());
+    let mut decoder = options.read_info(&image[..]).unwrap();
+    while decoder.read_next_frame().unwrap().is_some() {}
+    assert!(decoder.app_extensions().is_empty());
+    assert_eq!(
+        *handler.sub_blocks.lock().unwrap(),
+        [
+            (vec![b'8'; 255], false),
+            (vec![b'8'; 45], true),
+            (vec![], true),
+        ]
+    );
+}
+
+#[test]
+fn animexts_loop_count() {
+    let mut encoder = Encoder::new(vec![], 1, 1, &[0, 0, 0]).unwrap();
+    encoder
+        .write_raw_extension(
+            gif::Extension::Application.into(),
+            &[b"ANIMEXTS1.0", &[1, 0, 0]],
+        )
+        .unwrap();
+    encoder
+        .write_frame(&Frame::from_indexed_pixels(1, 1, vec![0], None))
+        .unwrap();
+    let image = encoder.into_inner().unwrap();
+
+    let mut options = DecodeOptions::new();
+    options.retain_app_extensions(true);
+    let decoder = options.read_info(&image[..]).unwrap();
+    assert_eq!(decoder.repeat(), Repeat::Infinite);
+    assert!(decoder.app_extensions().is_empty());
+}
+
+#[test]
+fn netscape_buffer_size_hint() {
+    let mut encoder = Encoder::new(vec![], 1, 1, &[0, 0, 0]).unwrap();
+    encoder.set_repeat(Repeat::Finite(2)).unwrap();
+    encoder.set_buffer_size_hint(0x0001_2345).unwrap();
+    encoder
+        .write_frame(&Frame::from_indexed_pixels(1, 1, vec![0], None))
+        .unwrap();
+    let image = encoder.into_inner().unwrap();
+
+    let decoder = DecodeOptions::new().read_info(&image[..]).unwrap();
+    assert_eq!(decoder.repeat(), Repeat::Finite(2));
+    assert_eq!(decoder.buffer_size_hint(), Some(0x0001_2345));
+
+    let image: &[u8] = include_bytes!("samples/beacon.gif");
+    let decoder = DecodeOptions::new().read_info(image).unwrap();
+    assert_eq!(decoder.buffer_size_hint(), None);
+}
+
+/// Decodes all frames with `read_next_frame` and the frame buffers with `fill_buffer`.
+fn decode_with_color_output(image: &[u8], color: gif::ColorOutput) -> Vec<Vec<u8>> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(color);
+    let mut decoder = options.clone().read_info(image).unwrap();
+    let mut frames = vec![];
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push(frame.buffer.to_vec());
+    }
+
+    let mut decoder = options.read_info(image).unwrap();
+    let mut n = 0;
+    while let Some(frame) = decoder.next_frame_info().unwrap() {
+        let (width, height) = (usize::from(frame.width), usize::from(frame.height));
+        assert_eq!(
+            decoder.buffer_size(),
+            width * height * color.bytes_per_pixel()
+        );
+        assert_eq!(decoder.line_length(), width * color.bytes_per_pixel());
+        let mut buffer = vec![0; decoder.buffer_size()];
+        // Fill the buffer in several parts, each a whole number of pixels
+        let chunk = (decoder.line_length() * 3).max(color.bytes_per_pixel());
+        for part in buffer.chunks_mut(chunk) {
+            assert!(decoder.fill_buffer(part).unwrap());
+        }
+        if !decoder.current_frame_info().unwrap().interlaced {
+            assert_eq!(buffer, frames[n]);
+        }
+        n += 1;
+    }
+    assert_eq!(n, frames.len());
+    frames
+}
+
+#[test]
+fn color_outputs() {
+    use gif::ColorOutput;
+
+    for image in [
+        &include_bytes!("samples/alpha_gif_a.gif")[..],
+        &include_bytes!("samples/interlaced.gif")[..],
+        &include_bytes!("samples/moon_impact.gif")[..],
+    ] {
+        let rgba = decode_with_color_output(image, ColorOutput::RGBA);
+        let luma = |p: &[u8]| {
+            ((2126 * u32::from(p[0]) + 7152 * u32::from(p[1]) + 722 * u32::from(p[2]) + 5000)
+                / 10000) as u8
+        };
+        // Converts an RGBA pixel and its luma to the expected output
+        type Convert = fn(&[u8], u8) -> Vec<u8>;
+        let outputs: [(ColorOutput, Convert); 5] = [
+            (ColorOutput::RGB, |p, _| p[..3].to_vec()),
+            (ColorOutput::BGRA, |p, _| vec![p[2], p[1], p[0], p[3]]),
+            (ColorOutput::PremultipliedRGBA, |p, _| {
+                if p[3] == 0 {
+                    vec![0; 4]
+                } else {
+                    p.to_vec()
+                }
+            }),
+            (ColorOutput::Luma, |_, luma| vec![luma]),
+            (ColorOutput::LumaAlpha, |p, luma| vec![luma, p[3]]),
+        ];
+        for (color, convert) in outputs {
+            let frames = decode_with_color_output(image, color);
+            assert_eq!(frames.len(), rgba.len());
+            for (frame, rgba) in frames.iter().zip(&rgba) {
+                let expected: Vec<u8> = rgba
+                    .chunks_exact(4)
+                    .flat_map(|p| convert(p, luma(p)))
+                    .collect();
+                assert!(*frame == expected, "{color:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn color_output_memory_limit() {
+    use gif::{ColorOutput, MemoryLimit};
+
+    // 100x100 pixels
+    let image: &[u8] = include_bytes!("samples/sample_big.gif");
+    let decoder = DecodeOptions::new().read_info(image).unwrap();
+    let pixels = usize::from(decoder.width()) * usize::from(decoder.height());
+    for color in [ColorOutput::Luma, ColorOutput::RGB, ColorOutput::BGRA] {
+        let mut options = DecodeOptions::new();
+        options.set_color_output(color);
+        let limit = (pixels * color.bytes_per_pixel()) as u64;
+        options.set_memory_limit(MemoryLimit::Bytes(limit.try_into().unwrap()));
+        let mut decoder = options.clone().read_info(image).unwrap();
+        assert!(decoder.read_next_frame().is_ok(), "{color:?}");
+        options.set_memory_limit(MemoryLimit::Bytes((limit - 1).try_into().unwrap()));
+        let mut decoder = options.read_info(image).unwrap();
+        assert!(decoder.read_next_frame().is_err(), "{color:?}");
+    }
+}
+
+#[test]
+fn strided_output() {
+    use gif::ColorOutput;
+
+    for image in [
+        &include_bytes!("samples/interlaced.gif")[..],
+        &include_bytes!("samples/moon_impact.gif")[..],
+    ] {
+        for color in [ColorOutput::Indexed, ColorOutput::RGB, ColorOutput::RGBA] {
+            let packed = decode_with_color_output(image, color);
+            let mut options = DecodeOptions::new();
+            options.set_color_output(color);
+            let mut decoder = options.read_info(image).unwrap();
+            let mut n = 0;
+            while decoder.next_frame_info().unwrap().is_some() {
+                let line_length = decoder.line_length();
+                let height = decoder.buffer_size() / line_length;
+                let (offset, stride) = (5, line_length + 7);
+                let mut buffer = vec![0xAA; offset + height * stride];
+                decoder
+                    .read_into_buffer_strided(&mut buffer, offset, stride)
+                    .unwrap();
+                for (row, expected) in packed[n].chunks_exact(line_length).enumerate() {
+                    let start = offset + row * stride;
+                    assert_eq!(&buffer[start..start + line_length], expected);
+                    assert!(buffer[start + line_length..start + stride]
+                        .iter()
+                        .all(|&b| b == 0xAA));
+                }
+                assert!(buffer[..offset].iter().all(|&b| b == 0xAA));
+                n += 1;
+            }
+            assert_eq!(n, packed.len());
+        }
+    }
+}
+
+#[test]
+fn read_into_canvas() {
+    use gif::ColorOutput;
+
+    let image: &[u8] = include_bytes!("samples/moon_impact.gif");
+    let packed = decode_with_color_output(image, ColorOutput::RGBA);
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.clone().read_info(image).unwrap();
+    let stride = usize::from(decoder.width()) * 4;
+    let mut canvas = vec![0; stride * usize::from(decoder.height())];
+    let frame = decoder.next_frame_info().unwrap().unwrap();
+    let (left, top) = (usize::from(frame.left), usize::from(frame.top));
+    let (width, height) = (usize::from(frame.width), usize::from(frame.height));
+    decoder
+        .read_into_buffer_at(&mut canvas, stride, left, top)
+        .unwrap();
+    for y in 0..height {
+        let start = (top + y) * stride + left * 4;
+        assert_eq!(
+            canvas[start..start + width * 4],
+            packed[0][y * width * 4..(y + 1) * width * 4]
+        );
+    }
+
+    // The frame does not fit at the right edge or into a short buffer
+    let mut decoder = options.read_info(image).unwrap();
+    decoder.next_frame_info().unwrap();
+    assert!(decoder
+        .read_into_buffer_at(&mut canvas, stride, 1, 0)
+        .is_err());
+    assert!(decoder
+        .read_into_buffer_strided(&mut canvas, 0, stride - 4)
+        .is_err());
+    assert!(decoder
+        .read_into_buffer_strided(&mut canvas[..stride * height - 1], 0, stride)
+        .is_err());
+}
+
+#[test]
+fn rows_shorter_than_lzw_codes() {
+    // Late codes expand to more pixels than fit in a row of this narrow frame
+    let pixels: Vec<u8> = (0..30).map(|i| i / 3).collect();
+    let palette: Vec<u8> = (0..16).flat_map(|i| [i * 16; 3]).collect();
+    let mut encoder = Encoder::new(vec![], 3, 10, &palette).unwrap();
+    encoder
+        .write_frame(&Frame::from_indexed_pixels(3, 10, pixels.clone(), None))
+        .unwrap();
+    let data = encoder.into_inner().unwrap();
+
+    for check_end_code in [false, true] {
+        let mut options = DecodeOptions::new();
+        options.check_lzw_end_code(check_end_code);
+        let mut decoder = options.read_info(&data[..]).unwrap();
+        decoder.next_frame_info().unwrap().unwrap();
+        let mut buffer = vec![0; 40];
+        decoder.read_into_buffer_strided(&mut buffer, 0, 4).unwrap();
+        let rows: Vec<u8> = buffer
+            .chunks(4)
+            .flat_map(|row| &row[..3])
+            .copied()
+            .collect();
+        assert_eq!(rows, pixels);
+    }
+}
+
+#[test]
+fn progressive_rows() {
+    use gif::DecodedRow;
+
+    // Each row is filled with its index
+    let (width, height) = (5u16, 20u16);
+    let pixels: Vec<u8> = (0..height).flat_map(|y| [y as u8; 5]).collect();
+    let palette: Vec<u8> = (0..32).flat_map(|i| [i * 8; 3]).collect();
+    let mut encoder = Encoder::new(vec![], width, height, &palette).unwrap();
+    let mut frame = Frame::from_indexed_pixels(width, height, pixels.clone(), None);
+    encoder.write_frame(&frame).unwrap();
+    frame.interlaced = true;
+    encoder.write_frame(&frame).unwrap();
+    let data = encoder.into_inner().unwrap();
+
+    let read_rows = |fill: bool| {
+        let mut options = DecodeOptions::new();
+        options.fill_interlaced_blocks(fill);
+        let mut decoder = options.read_info(&data[..]).unwrap();
+        let mut frames = Vec::new();
+        while decoder.next_frame_info().unwrap().is_some() {
+            let mut buffer = vec![0xFF; decoder.buffer_size()];
+            let mut rows = Vec::new();
+            decoder
+                .read_into_buffer_progressive(&mut buffer, |row, buffer| {
+                    let start = usize::from(row.y * width);
+                    let end = usize::from((row.y + row.rows) * width);
+                    assert!(buffer[start..end].iter().all(|&p| p == row.y as u8));
+                    if row.pass == Some(1) && row.y == 16 && fill {
+                        // The first pass is complete and covers the whole frame
+                        for (y, line) in buffer.chunks(5).enumerate() {
+                            assert!(line.iter().all(|&p| usize::from(p) == y - y % 8));
+                        }
+                    }
+                    rows.push(row);
+                })
+                .unwrap();
+            assert_eq!(buffer, pixels);
+            frames.push(rows);
+        }
+        frames
+    };
+
+    for fill in [false, true] {
+        let frames = read_rows(fill);
+        assert_eq!(frames.len(), 2);
+        let expected: Vec<_> = (0..height)
+            .map(|y| DecodedRow {
+                y,
+                pass: None,
+                rows: 1,
+            })
+            .collect();
+        assert_eq!(frames[0], expected);
+
+        let passes = [(1, 0, 8), (2, 4, 8), (3, 2, 4), (4, 1, 2)];
+        let expected: Vec<_> = passes
+            .iter()
+            .flat_map(|&(pass, start, step)| {
+                (start..height).step_by(step).map(move |y| DecodedRow {
+                    y,
+                    pass: Some(pass),
+                    rows: if fill {
+                        (8 >> (pass - 1)).min(height - y)
+                    } else {
+                        1
+                    },
+                })
+            })
+            .collect();
+        assert_eq!(frames[1], expected);
+    }
+}
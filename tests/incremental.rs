@@ -0,0 +1,181 @@
+#![cfg(feature = "std")]
+
+use gif::{ColorOutput, DecodeOptions, Encoder, Frame, IncrementalDecoder};
+
+const SAMPLES: [&[u8]; 4] = [
+    include_bytes!("samples/sample_1.gif"),
+    include_bytes!("samples/interlaced.gif"),
+    include_bytes!("samples/moon_impact.gif"),
+    include_bytes!("samples/beacon_xmp.gif"),
+];
+
+/// Position, size and delay, transparent index, palette and pixels.
+type FrameData = ([u16; 5], Option<u8>, Option<Vec<u8>>, Vec<u8>);
+
+fn frame_data(frame: &Frame<'_>) -> FrameData {
+    (
+        [
+            frame.left,
+            frame.top,
+            frame.width,
+            frame.height,
+            frame.delay,
+        ],
+        frame.transparent,
+        frame.palette.clone(),
+        frame.buffer.to_vec(),
+    )
+}
+
+fn decode_sync(image: &[u8], options: DecodeOptions) -> Vec<FrameData> {
+    let mut decoder = options.read_info(image).unwrap();
+    let mut frames = vec![];
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push(frame_data(frame));
+    }
+    frames
+}
+
+fn decode_incremental(image: &[u8], chunk: usize, options: DecodeOptions) -> Vec<FrameData> {
+    let mut decoder = options.incremental();
+    let mut frames = vec![];
+    for data in image.chunks(chunk) {
+        decoder.push(data).unwrap();
+        frames.extend(core::iter::from_fn(|| decoder.next_frame()).map(|f| frame_data(&f)));
+    }
+    decoder.finish().unwrap();
+    assert!(decoder.is_done());
+    frames.extend(core::iter::from_fn(|| decoder.next_frame()).map(|f| frame_data(&f)));
+    assert_eq!(decoder.frames_decoded(), frames.len());
+    frames
+}
+
+#[test]
+fn matches_sync_decoder() {
+    for image in SAMPLES {
+        for color in [ColorOutput::Indexed, ColorOutput::RGBA] {
+            let mut options = DecodeOptions::new();
+            options.set_color_output(color);
+            let expected = decode_sync(image, options.clone());
+            for chunk in [1, 7, 4096] {
+                assert_eq!(decode_incremental(image, chunk, options.clone()), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn lzw_data() {
+    let mut options = DecodeOptions::new();
+    options.skip_frame_decoding(true);
+    for image in SAMPLES {
+        let expected = decode_sync(image, options.clone());
+        assert_eq!(decode_incremental(image, 3, options.clone()), expected);
+    }
+}
+
+#[test]
+fn metadata() {
+    let image = include_bytes!("samples/beacon_xmp.gif");
+    let sync = DecodeOptions::new().read_info(&image[..]).unwrap();
+    let mut decoder = IncrementalDecoder::new();
+    decoder.push(&image[..10]).unwrap();
+    assert!(!decoder.has_header());
+    decoder.push(&image[10..]).unwrap();
+    assert!(decoder.has_header());
+    assert_eq!(decoder.width(), sync.width());
+    assert_eq!(decoder.height(), sync.height());
+    assert_eq!(decoder.xmp_metadata(), sync.xmp_metadata());
+    assert_eq!(decoder.global_palette(), sync.global_palette());
+    assert_eq!(decoder.repeat(), sync.repeat());
+}
+
+/// A frame of 4×16 pixels with each row filled with its index.
+fn rows_image(interlaced: bool) -> (Vec<u8>, Vec<u8>) {
+    let pixels: Vec<u8> = (0..16).flat_map(|y| [y; 4]).collect();
+    let palette: Vec<u8> = (0..16).flat_map(|i| [i * 16; 3]).collect();
+    let mut frame = Frame::from_indexed_pixels(4, 16, pixels.clone(), None);
+    frame.interlaced = interlaced;
+    let mut encoder = Encoder::new(vec![], 4, 16, &palette).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    (encoder.into_inner().unwrap(), pixels)
+}
+
+#[test]
+fn partial_frame() {
+    let (image, pixels) = rows_image(false);
+    let mut decoder = IncrementalDecoder::new();
+    let mut last_progress = 0.0;
+    for &byte in &image {
+        decoder.push(&[byte]).unwrap();
+        let progress = decoder.frame_progress(0);
+        assert!(progress >= last_progress);
+        last_progress = progress;
+        if let Some(buffer) = decoder.partial_frame_buffer() {
+            assert_eq!(decoder.frames_decoded(), 0);
+            assert_eq!(decoder.current_frame_info().unwrap().height, 16);
+            // Complete rows are the top rows, followed by zeroes
+            let rows = decoder.rows_decoded() * 4;
+            assert_eq!(buffer[..rows], pixels[..rows]);
+            assert!(progress < 1.0);
+            assert!(progress >= rows as f32 / pixels.len() as f32);
+        }
+    }
+    assert_eq!(decoder.frame_progress(0), 1.0);
+    assert_eq!(decoder.frame_progress(1), 0.0);
+    assert!(decoder.partial_frame_buffer().is_none());
+    assert_eq!(&*decoder.next_frame().unwrap().buffer, &pixels[..]);
+    decoder.finish().unwrap();
+}
+
+#[test]
+fn partial_interlaced_frame() {
+    let (image, pixels) = rows_image(true);
+    let mut options = DecodeOptions::new();
+    options.fill_interlaced_blocks(true);
+    let mut decoder = options.incremental();
+    let mut seen_first_pass = false;
+    for &byte in &image {
+        decoder.push(&[byte]).unwrap();
+        // After the first pass, rows 0 and 8 cover the whole frame. Row 4 of the second pass
+        // may be decoded partially.
+        if decoder.rows_decoded() == 2 {
+            let buffer = decoder.partial_frame_buffer().unwrap();
+            for (y, row) in buffer.chunks(4).enumerate().filter(|&(y, _)| y != 4) {
+                assert!(row.iter().all(|&p| usize::from(p) == y - y % 8));
+            }
+            seen_first_pass = true;
+        }
+    }
+    assert!(seen_first_pass);
+    let frame = decoder.next_frame().unwrap();
+    assert!(!frame.interlaced);
+    assert_eq!(&*frame.buffer, &pixels[..]);
+}
+
+#[test]
+fn truncated() {
+    let image = include_bytes!("samples/moon_impact.gif");
+    for len in [0, 10, 800, image.len() / 2, image.len() - 1] {
+        let data = &image[..len];
+        let sync = (|| {
+            let mut decoder = DecodeOptions::new().read_info(data)?;
+            while decoder.read_next_frame()?.is_some() {}
+            Ok::<_, gif::DecodingError>(())
+        })();
+        let mut decoder = IncrementalDecoder::new();
+        decoder.push(data).unwrap();
+        let result = decoder.finish();
+        assert_eq!(result.is_err(), sync.is_err(), "{len}");
+        // Only a missing trailer is accepted
+        if len < image.len() - 1 {
+            assert!(result.is_err(), "{len}");
+        }
+    }
+}
+
+#[test]
+fn invalid_data() {
+    let mut decoder = IncrementalDecoder::new();
+    assert!(decoder.push(b"GIF89a\0\0\0\0\0\0\0\x99\0\0").is_err());
+}
@@ -0,0 +1,308 @@
+#![cfg(feature = "std")]
+
+use gif::optimize::optimize_frames;
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, Encoder, EncoderBuilder, Frame, Screen};
+
+const WIDTH: u16 = 32;
+const HEIGHT: u16 = 24;
+const PALETTE: [u8; 12] = [0, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0, 0, 0, 0xFF];
+
+/// Renders a frame as it is expected on the canvas, with transparent background.
+fn expected_rgba(frame: &Frame<'_>, global_palette: &[u8]) -> Vec<u8> {
+    let palette = frame.palette.as_deref().unwrap_or(global_palette);
+    frame
+        .buffer
+        .iter()
+        .flat_map(|&i| match frame.transparent {
+            Some(t) if t == i => [0; 4],
+            _ => {
+                let i = usize::from(i) * 3;
+                [palette[i], palette[i + 1], palette[i + 2], 0xFF]
+            }
+        })
+        .collect()
+}
+
+fn encode(frames: &[Frame<'_>], global_palette: &[u8]) -> Vec<u8> {
+    let mut encoder = Encoder::new(vec![], WIDTH, HEIGHT, global_palette).unwrap();
+    for frame in frames {
+        encoder.write_frame(frame).unwrap();
+    }
+    encoder.into_inner().unwrap()
+}
+
+/// Composites the frames, over the opaque background color of the file if `opaque` is set.
+fn composite(image: &[u8], opaque: bool) -> Vec<Vec<u8>> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(image).unwrap();
+    let mut screen = Screen::new_decoder(&decoder);
+    if opaque {
+        screen.set_bg_color(decoder.bg_color());
+    }
+    let mut canvases = vec![];
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        screen.blit_frame(frame).unwrap();
+        canvases.push(screen.pixels_rgba().to_vec());
+    }
+    canvases
+}
+
+/// Optimizes the frames and checks that compositing gives the original frames.
+fn check(mut frames: Vec<Frame<'static>>, global_palette: &[u8]) -> (usize, usize) {
+    let expected: Vec<_> = frames
+        .iter()
+        .map(|f| expected_rgba(f, global_palette))
+        .collect();
+    let unoptimized = encode(&frames, global_palette);
+    optimize_frames(WIDTH, HEIGHT, Some(global_palette), &mut frames).unwrap();
+    let optimized = encode(&frames, global_palette);
+    assert_eq!(composite(&optimized, false), expected);
+
+    // Decoders that draw the background color show it instead of transparent pixels
+    if global_palette.len() < 256 * 3 {
+        let mut palette = global_palette.to_vec();
+        palette.extend([0x80; 3]);
+        let mut builder = EncoderBuilder::new(WIDTH, HEIGHT);
+        builder.set_global_palette(&palette);
+        builder.set_background_color((global_palette.len() / 3) as u8);
+        let mut encoder = builder.build(vec![]).unwrap();
+        for frame in &frames {
+            encoder.write_frame(frame).unwrap();
+        }
+        let expected: Vec<Vec<u8>> = expected
+            .iter()
+            .map(|canvas| {
+                canvas
+                    .chunks_exact(4)
+                    .flat_map(|p| {
+                        if p[3] == 0 {
+                            [0x80, 0x80, 0x80, 0xFF]
+                        } else {
+                            [p[0], p[1], p[2], p[3]]
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(composite(&encoder.into_inner().unwrap(), true), expected);
+    }
+    (unoptimized.len(), optimized.len())
+}
+
+/// A full-canvas frame filled with `background`, with `f` drawing on top.
+fn frame(background: u8, f: impl Fn(usize, usize) -> Option<u8>) -> Frame<'static> {
+    let mut pixels = vec![];
+    for y in 0..usize::from(HEIGHT) {
+        for x in 0..usize::from(WIDTH) {
+            pixels.push(f(x, y).unwrap_or(background));
+        }
+    }
+    Frame::from_indexed_pixels(WIDTH, HEIGHT, pixels, None)
+}
+
+fn square(x: usize, y: usize, left: usize, top: usize, color: u8) -> Option<u8> {
+    (x >= left && x < left + 4 && y >= top && y < top + 4).then_some(color)
+}
+
+#[test]
+fn moving_square() {
+    let frames = (0..10)
+        .map(|i| frame(0, |x, y| square(x, y, 2 * i, i, 1)))
+        .collect();
+    let (unoptimized, optimized) = check(frames, &PALETTE);
+    assert!(optimized < unoptimized, "{optimized} >= {unoptimized}");
+}
+
+#[test]
+fn crops_to_changes() {
+    let mut frames: Vec<_> = (0..3)
+        .map(|i| frame(0, |x, y| square(x, y, 8, 8, 1 + i)))
+        .collect();
+    frames.push(frames[2].clone());
+    optimize_frames(WIDTH, HEIGHT, Some(&PALETTE), &mut frames).unwrap();
+    assert_eq!((frames[0].width, frames[0].height), (WIDTH, HEIGHT));
+    for frame in &frames[1..3] {
+        assert_eq!(
+            (frame.left, frame.top, frame.width, frame.height),
+            (8, 8, 4, 4)
+        );
+    }
+    // An unchanged frame is reduced to a single transparent pixel
+    assert_eq!((frames[3].width, frames[3].height), (1, 1));
+    assert_eq!(Some(frames[3].buffer[0]), frames[3].transparent);
+    assert_eq!(frames[3].dispose, DisposalMethod::Keep);
+}
+
+#[test]
+fn blinking_overlay_uses_previous() {
+    let frames = vec![
+        frame(0, |x, y| square(x, y, 0, 0, 1).or(square(x, y, 20, 16, 2))),
+        frame(0, |x, y| square(x, y, 0, 0, 3).or(square(x, y, 20, 16, 2))),
+        frame(0, |x, y| square(x, y, 0, 0, 1).or(square(x, y, 20, 16, 3))),
+    ];
+    let mut optimized = frames.clone();
+    optimize_frames(WIDTH, HEIGHT, Some(&PALETTE), &mut optimized).unwrap();
+    assert_eq!(optimized[1].dispose, DisposalMethod::Previous);
+    assert_eq!((optimized[2].left, optimized[2].top), (20, 16));
+    check(frames, &PALETTE);
+}
+
+#[test]
+fn transparency() {
+    let transparent = |mut frame: Frame<'static>| {
+        frame.transparent = Some(0);
+        frame
+    };
+    let frames = vec![
+        // Transparent background with a moving square
+        transparent(frame(0, |x, y| square(x, y, 0, 0, 1))),
+        transparent(frame(0, |x, y| square(x, y, 2, 2, 1))),
+        // Opaque, then transparent outside of the last frame
+        frame(2, |x, y| square(x, y, 4, 4, 1)),
+        frame(2, |x, y| square(x, y, 6, 6, 1)),
+        transparent(frame(0, |x, y| square(x, y, 6, 6, 3))),
+        transparent(frame(0, |_, _| None)),
+    ];
+    check(frames, &PALETTE);
+}
+
+#[test]
+fn local_palettes() {
+    let local = |mut frame: Frame<'static>, palette: &[u8]| {
+        frame.palette = Some(palette.to_vec());
+        frame
+    };
+    let frames = vec![
+        local(frame(0, |x, y| square(x, y, 0, 0, 1)), &PALETTE[..6]),
+        // Same colors with different indices
+        local(
+            frame(1, |x, y| square(x, y, 0, 0, 0)),
+            &[0xFF, 0, 0, 0, 0, 0],
+        ),
+        frame(0, |x, y| square(x, y, 3, 3, 2)),
+    ];
+    let mut optimized = frames.clone();
+    optimize_frames(WIDTH, HEIGHT, Some(&PALETTE), &mut optimized).unwrap();
+    // Nothing changed on the canvas, a transparent entry was added to the local palette
+    assert_eq!((optimized[1].width, optimized[1].height), (1, 1));
+    assert_eq!(optimized[1].transparent, Some(2));
+    check(frames, &PALETTE);
+}
+
+#[test]
+fn full_palette() {
+    let palette: Vec<u8> = (0..=255).flat_map(|i| [i, i, i]).collect();
+    let all_colors = |shift: usize| frame(0, move |x, y| Some(((x + y * 32 + shift) % 256) as u8));
+    let frames = vec![all_colors(0), all_colors(0), all_colors(1)];
+    let mut optimized = frames.clone();
+    optimize_frames(WIDTH, HEIGHT, Some(&palette), &mut optimized).unwrap();
+    assert_eq!(optimized[1].transparent, None);
+    check(frames, &palette);
+}
+
+#[test]
+fn invalid_frames() {
+    let mut frames = vec![Frame::from_indexed_pixels(2, 2, vec![0; 4], None)];
+    assert!(optimize_frames(WIDTH, HEIGHT, Some(&PALETTE), &mut frames).is_err());
+    let mut frames = vec![frame(0, |_, _| None)];
+    assert!(optimize_frames(WIDTH, HEIGHT, None, &mut frames).is_err());
+}
+
+fn rgba_frames(frames: &[Frame<'_>], global_palette: &[u8]) -> Vec<Vec<u8>> {
+    frames
+        .iter()
+        .map(|f| expected_rgba(f, global_palette))
+        .collect()
+}
+
+#[test]
+fn frame_palette() {
+    // Entry 1 is unused, entries 2 and 3 are duplicates and 4 is transparent
+    let palette = vec![0, 0, 0, 9, 9, 9, 0xFF, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0];
+    let mut frame =
+        Frame::from_palette_pixels(2, 3, vec![0, 2, 3, 4, 4, 2], palette.clone(), Some(4));
+    let expected = expected_rgba(&frame, &[]);
+    frame.optimize_palette();
+    assert_eq!(
+        frame.palette.as_deref(),
+        Some(&[0, 0, 0, 0xFF, 0, 0, 0, 0, 0][..])
+    );
+    assert_eq!(frame.transparent, Some(2));
+    assert_eq!(&*frame.buffer, [0, 1, 1, 2, 2, 1]);
+    assert_eq!(expected_rgba(&frame, &[]), expected);
+
+    let mut frame = Frame::from_indexed_pixels(1, 1, vec![3], None);
+    frame.optimize_palette();
+    assert_eq!(&*frame.buffer, [3]);
+}
+
+#[test]
+fn promote_to_global_palette() {
+    let local = |pixels: Vec<u8>, palette: &[u8], transparent| {
+        Frame::from_palette_pixels(2, 2, pixels, palette.to_vec(), transparent)
+    };
+    let mut frames = vec![
+        local(vec![0, 1, 1, 0], &PALETTE, None),
+        local(
+            vec![0, 1, 0, 3],
+            &[0, 0xFF, 0, 0xFF, 0, 0, 1, 2, 3, 4, 5, 6],
+            Some(3),
+        ),
+        Frame::from_indexed_pixels(2, 2, vec![1, 1, 1, 1], None),
+    ];
+    let global = vec![0, 0, 0, 0xFF, 0, 0];
+    let expected = rgba_frames(&frames, &global);
+    let mut optimized_global = global.clone();
+    gif::optimize::optimize_palettes(&mut optimized_global, &mut frames).unwrap();
+    assert!(frames.iter().all(|f| f.palette.is_none()));
+    assert_eq!(optimized_global, [0, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0, 0, 0, 0]);
+    assert_eq!(frames[1].transparent, Some(3));
+    assert_eq!(rgba_frames(&frames, &optimized_global), expected);
+}
+
+#[test]
+fn keep_small_local_palettes() {
+    // Each frame uses two colors, but together they use 256
+    let mut frames: Vec<_> = (0..128u8)
+        .map(|i| {
+            let palette = [i, 0, 0, i, 0xFF, 0, 0, 0, 0xFF];
+            Frame::from_palette_pixels(2, 1, vec![0, 1], palette.to_vec(), None)
+        })
+        .collect();
+    let mut global = PALETTE.to_vec();
+    let expected = rgba_frames(&frames, &global);
+    gif::optimize::optimize_palettes(&mut global, &mut frames).unwrap();
+    assert!(global.is_empty());
+    assert!(frames
+        .iter()
+        .all(|f| f.palette.as_ref().unwrap().len() == 6));
+    assert_eq!(rgba_frames(&frames, &global), expected);
+}
+
+#[test]
+fn compact_global_palette() {
+    let palette: Vec<u8> = (0..=255).flat_map(|i| [i, i, i]).collect();
+    let mut frames = vec![
+        Frame::from_indexed_pixels(2, 1, vec![200, 100], None),
+        Frame::from_indexed_pixels(2, 1, vec![100, 50], Some(50)),
+    ];
+    let mut global = palette.clone();
+    let expected = rgba_frames(&frames, &global);
+    let unoptimized = encode_small(&frames, &palette);
+    gif::optimize::optimize_palettes(&mut global, &mut frames).unwrap();
+    assert_eq!(global, [100, 100, 100, 200, 200, 200, 0, 0, 0]);
+    assert_eq!(rgba_frames(&frames, &global), expected);
+    assert!(encode_small(&frames, &global).len() < unoptimized.len());
+
+    let mut frames = vec![Frame::default()];
+    assert!(gif::optimize::optimize_palettes(&mut vec![], &mut frames).is_err());
+}
+
+fn encode_small(frames: &[Frame<'_>], global_palette: &[u8]) -> Vec<u8> {
+    let mut encoder = Encoder::new(vec![], 2, 1, global_palette).unwrap();
+    for frame in frames {
+        encoder.write_frame(frame).unwrap();
+    }
+    encoder.into_inner().unwrap()
+}
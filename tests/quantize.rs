@@ -0,0 +1,570 @@
+use gif::quantize::{AnimationPalette, Dither, FrameOptions, MedianCut, Octree, Quantizer};
+use gif::Frame;
+
+const WIDTH: u16 = 64;
+const HEIGHT: u16 = 64;
+
+/// An RGBA gradient with 4096 distinct colors.
+fn gradient() -> Vec<u8> {
+    (0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).map(move |x| (x, y)))
+        .flat_map(|(x, y)| [(x * 4) as u8, (y * 4) as u8, ((x + y) * 2) as u8, 0xFF])
+        .collect()
+}
+
+/// Mean squared error per channel of the frame compared to the original pixels.
+fn mean_squared_error(frame: &Frame<'_>, rgba: &[u8]) -> u64 {
+    let palette = frame.palette.as_deref().unwrap();
+    let sum: u64 = frame
+        .buffer
+        .iter()
+        .zip(rgba.chunks_exact(4))
+        .map(|(&index, pixel)| {
+            let entry = &palette[usize::from(index) * 3..][..3];
+            entry
+                .iter()
+                .zip(pixel)
+                .map(|(&a, &b)| (i64::from(a) - i64::from(b)).pow(2) as u64)
+                .sum::<u64>()
+        })
+        .sum();
+    sum / (rgba.len() as u64 / 4 * 3)
+}
+
+fn check_quantizer(quantizer: &dyn Quantizer, max_error: u64) {
+    let original = gradient();
+    let frame = Frame::from_rgba_with_quantizer(WIDTH, HEIGHT, &mut original.clone(), quantizer);
+    let palette = frame.palette.as_deref().unwrap();
+    assert!(!palette.is_empty() && palette.len() <= 256 * 3);
+    assert_eq!(palette.len() % 3, 0);
+    assert_eq!(frame.buffer.len(), usize::from(WIDTH) * usize::from(HEIGHT));
+    assert!(frame
+        .buffer
+        .iter()
+        .all(|&index| usize::from(index) < palette.len() / 3));
+    assert_eq!(frame.transparent, None);
+    let error = mean_squared_error(&frame, &original);
+    assert!(error < max_error, "mean squared error {error} is too high");
+}
+
+#[test]
+fn median_cut() {
+    check_quantizer(&MedianCut, 20);
+}
+
+#[test]
+fn octree() {
+    check_quantizer(&Octree, 40);
+}
+
+#[test]
+#[cfg(feature = "color_quant")]
+fn neuquant() {
+    // The network learns from a sample of the pixels, less precise for small images
+    check_quantizer(&gif::quantize::NeuQuant::new(10), 128);
+}
+
+#[test]
+fn palette_size_limit() {
+    let pixels = gradient();
+    for max_colors in [1, 2, 7, 16, 255] {
+        for quantizer in [&MedianCut as &dyn Quantizer, &Octree] {
+            let palette = quantizer.palette(&pixels, max_colors);
+            assert!(!palette.is_empty());
+            assert!(palette.len() <= max_colors * 3, "{max_colors} colors");
+        }
+    }
+}
+
+#[test]
+fn few_colors_are_exact() {
+    let mut pixels: Vec<u8> = [[1, 2, 3, 0xFF], [4, 5, 6, 0xFF]]
+        .iter()
+        .cycle()
+        .take(16)
+        .flatten()
+        .copied()
+        .collect();
+    let frame = Frame::from_rgba_with_quantizer(4, 4, &mut pixels, &MedianCut);
+    assert_eq!(frame.palette.as_deref(), Some(&[1, 2, 3, 4, 5, 6][..]));
+    assert_eq!(&*frame.buffer, &[0, 1].repeat(8)[..]);
+}
+
+#[test]
+fn transparent_entry() {
+    let mut pixels = gradient();
+    // Make the left half transparent, with colors that still differ
+    for row in pixels.chunks_exact_mut(usize::from(WIDTH) * 4) {
+        for pixel in row[..usize::from(WIDTH) * 2].chunks_exact_mut(4) {
+            pixel[3] = 0;
+        }
+    }
+    let original = pixels.clone();
+    for quantizer in [&MedianCut as &dyn Quantizer, &Octree] {
+        let frame = Frame::from_rgba_with_quantizer(WIDTH, HEIGHT, &mut pixels, quantizer);
+        let transparent = frame.transparent.expect("transparent index");
+        let palette = frame.palette.as_deref().unwrap();
+        assert!(palette.len() <= 256 * 3);
+        for (&index, pixel) in frame.buffer.iter().zip(original.chunks_exact(4)) {
+            assert_eq!(index == transparent, pixel[3] == 0);
+        }
+    }
+}
+
+#[test]
+fn rgb_with_quantizer() {
+    let rgba = gradient();
+    let rgb: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+    let from_rgb = Frame::from_rgb_with_quantizer(WIDTH, HEIGHT, &rgb, &Octree);
+    let from_rgba = Frame::from_rgba_with_quantizer(WIDTH, HEIGHT, &mut rgba.clone(), &Octree);
+    assert_eq!(from_rgb.palette, from_rgba.palette);
+    assert_eq!(from_rgb.buffer, from_rgba.buffer);
+}
+
+/// A horizontal gray ramp, 256 pixels wide.
+fn gray_ramp(height: u16) -> Vec<u8> {
+    (0..height)
+        .flat_map(|_| 0..=255u8)
+        .flat_map(|v| [v, v, v, 0xFF])
+        .collect()
+}
+
+/// Mean absolute difference of 16×16 block averages, the visible banding.
+fn block_error(frame: &Frame<'_>, palette: &[u8], rgba: &[u8]) -> u64 {
+    let width = usize::from(frame.width);
+    let mut error = 0;
+    for by in 0..usize::from(frame.height) / 16 {
+        for bx in 0..width / 16 {
+            let (mut original, mut mapped) = (0, 0);
+            for y in by * 16..by * 16 + 16 {
+                for x in bx * 16..bx * 16 + 16 {
+                    let n = y * width + x;
+                    original += u64::from(rgba[n * 4]);
+                    mapped += u64::from(palette[usize::from(frame.buffer[n]) * 3]);
+                }
+            }
+            error += original.abs_diff(mapped) / 256;
+        }
+    }
+    error
+}
+
+#[test]
+fn dithering_reduces_banding() {
+    let rgba = gray_ramp(32);
+    let palette: Vec<u8> = (0..8u8).flat_map(|v| [v * 36; 3]).collect();
+    let mut options = FrameOptions::new();
+    let plain = options.map_rgba(256, 32, &rgba, &palette, None);
+    let banding = block_error(&plain, &palette, &rgba);
+    for dither in [
+        Dither::FloydSteinberg,
+        Dither::Atkinson,
+        Dither::Sierra,
+        Dither::Bayer,
+    ] {
+        options.set_dither(dither);
+        let dithered = options.map_rgba(256, 32, &rgba, &palette, None);
+        let error = block_error(&dithered, &palette, &rgba);
+        assert!(error * 2 < banding, "{dither:?}: {error} vs {banding}");
+        assert_eq!(
+            dithered.buffer,
+            options.map_rgba(256, 32, &rgba, &palette, None).buffer,
+            "{dither:?} is not deterministic"
+        );
+    }
+}
+
+#[test]
+fn dither_options() {
+    let rgba = gray_ramp(8);
+    let palette = [0, 0, 0, 0xFF, 0xFF, 0xFF];
+    let mut options = FrameOptions::new();
+    let plain = options.map_rgba(256, 8, &rgba, &palette, None);
+    options.set_dither(Dither::FloydSteinberg);
+    options.set_dither_strength(0.0);
+    assert_eq!(
+        options.map_rgba(256, 8, &rgba, &palette, None).buffer,
+        plain.buffer
+    );
+    options.set_dither_strength(1.0);
+    let serpentine = options.map_rgba(256, 8, &rgba, &palette, None);
+    options.set_serpentine(false);
+    let raster = options.map_rgba(256, 8, &rgba, &palette, None);
+    assert_ne!(serpentine.buffer, raster.buffer);
+    // The first row is scanned left to right either way
+    assert_eq!(serpentine.buffer[..256], raster.buffer[..256]);
+}
+
+#[test]
+fn quantize_with_dithering() {
+    let mut options = FrameOptions::new();
+    options.set_dither(Dither::Sierra);
+    let original = gradient();
+    let frame = options.quantize_rgba(WIDTH, HEIGHT, &mut original.clone(), &MedianCut);
+    assert_eq!(frame.palette.as_deref().unwrap().len(), 256 * 3);
+    assert!(mean_squared_error(&frame, &original) < 40);
+}
+
+#[test]
+fn map_transparent() {
+    let mut rgba = gray_ramp(4);
+    for pixel in rgba.chunks_exact_mut(4).step_by(3) {
+        pixel[3] = 0;
+    }
+    // The transparent entry has the color of the ramp start, but is never used for it
+    let palette = [0, 0, 0, 0, 0, 0, 0xFF, 0xFF, 0xFF];
+    let mut options = FrameOptions::new();
+    options.set_dither(Dither::Atkinson);
+    let frame = options.map_rgba(256, 4, &rgba, &palette, Some(0));
+    assert_eq!(frame.transparent, Some(0));
+    assert_eq!(frame.palette, None);
+    for (&index, pixel) in frame.buffer.iter().zip(rgba.chunks_exact(4)) {
+        assert_eq!(index == 0, pixel[3] == 0);
+    }
+}
+
+#[test]
+fn unchanged_pixels_are_stable() {
+    let first = gray_ramp(32);
+    let mut second = first.clone();
+    // Change a block in the middle
+    for y in 8..16 {
+        second[(y * 256 + 100) * 4..(y * 256 + 140) * 4].fill(0x80);
+    }
+    let palette: Vec<u8> = (0..4u8).flat_map(|v| [v * 85; 3]).collect();
+    let mut options = FrameOptions::new();
+    options.set_dither(Dither::FloydSteinberg);
+    let previous = options.map_rgba(256, 32, &first, &palette, None);
+    let next = options.map_rgba_following(&previous, &first, &second, &palette);
+    let independent = options.map_rgba(256, 32, &second, &palette, None);
+
+    let unchanged = |(n, _): &(usize, _)| first[n * 4..][..4] == second[n * 4..][..4];
+    let changed_elsewhere = previous
+        .buffer
+        .iter()
+        .zip(&*independent.buffer)
+        .enumerate()
+        .filter(unchanged)
+        .filter(|(_, (a, b))| a != b)
+        .count();
+    // Without the previous frame the diffused error changes other pixels too
+    assert!(changed_elsewhere > 0);
+    for (n, (a, b)) in previous.buffer.iter().zip(&*next.buffer).enumerate() {
+        if first[n * 4..][..4] == second[n * 4..][..4] {
+            assert_eq!(a, b, "pixel {n}");
+        }
+    }
+}
+
+#[test]
+fn try_map_errors() {
+    use gif::{EncodingError, EncodingFormatError};
+
+    macro_rules! assert_format_error {
+        ($result:expr, $err:pat) => {
+            assert!(matches!($result, Err(EncodingError::Format($err))))
+        };
+    }
+    let rgba = gray_ramp(2);
+    let palette = [0, 0, 0, 0xFF, 0xFF, 0xFF];
+    let options = FrameOptions::new();
+    assert_format_error!(
+        options.try_quantize_rgba(256, 3, &mut rgba.clone(), &MedianCut),
+        EncodingFormatError::InvalidFrameSize
+    );
+    assert_format_error!(
+        options.try_map_rgba(256, 3, &rgba, &palette, None),
+        EncodingFormatError::InvalidFrameSize
+    );
+    assert_format_error!(
+        options.try_map_rgba(256, 2, &rgba, &[], None),
+        EncodingFormatError::MissingColorPalette
+    );
+    assert_format_error!(
+        options.try_map_rgba(256, 2, &rgba, &[0; 257 * 3], None),
+        EncodingFormatError::TooManyColors
+    );
+
+    let previous = options.try_map_rgba(256, 2, &rgba, &palette, None).unwrap();
+    assert_format_error!(
+        options.try_map_rgba_following(&previous, &rgba[4..], &rgba, &palette),
+        EncodingFormatError::InvalidFrameSize
+    );
+    assert_format_error!(
+        options.try_map_rgba_following(&previous, &rgba, &rgba, &[]),
+        EncodingFormatError::MissingColorPalette
+    );
+    let next = options.try_map_rgba_following(&previous, &rgba, &rgba, &palette);
+    assert_eq!(next.unwrap().buffer, previous.buffer);
+}
+
+/// Decodes a GIF to RGBA frames, all of the same size.
+#[cfg(feature = "std")]
+fn decode_rgba(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(data).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push(frame.buffer.to_vec());
+    }
+    frames
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn animation_few_colors() {
+    let frames: Vec<Vec<u8>> = (0..3u8)
+        .map(|i| {
+            (0..64u8)
+                .flat_map(|n| [n % 4 * 60 + i, 0, 0xFF, if n == 5 { 0 } else { 0xFF }])
+                .collect()
+        })
+        .collect();
+    let sample: Vec<&[u8]> = frames.iter().map(|frame| &frame[..]).collect();
+    let mut animation = AnimationPalette::new(MedianCut, FrameOptions::new(), &sample);
+    // 12 distinct colors and a transparent entry
+    assert_eq!(animation.palette().len(), 13 * 3);
+    assert_eq!(animation.transparent(), Some(12));
+
+    let mut data = Vec::new();
+    let mut encoder = gif::Encoder::new(&mut data, 8, 8, animation.palette()).unwrap();
+    for pixels in &frames {
+        let frame = animation.frame(8, 8, &mut pixels.clone());
+        assert_eq!(frame.palette, None);
+        assert_eq!(frame.transparent, Some(12));
+        encoder.write_frame(&frame).unwrap();
+    }
+    drop(encoder);
+
+    let decoded = decode_rgba(&data);
+    assert_eq!(decoded.len(), 3);
+    for (decoded, original) in decoded.iter().zip(&frames) {
+        for (a, b) in decoded.chunks_exact(4).zip(original.chunks_exact(4)) {
+            if b[3] == 0 {
+                assert_eq!(a[3], 0);
+            } else {
+                assert_eq!(a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn animation_global_palette() {
+    let frames: Vec<Vec<u8>> = (0..4u8)
+        .map(|i| {
+            let mut frame = gradient();
+            frame.rotate_left(usize::from(i) * 4 * 7);
+            frame
+        })
+        .collect();
+    let sample = [&frames[0][..], &frames[2][..]];
+    let mut animation = AnimationPalette::new(Octree, FrameOptions::new(), &sample);
+    assert_eq!(animation.transparent(), None);
+    assert!(animation.palette().len() <= 256 * 3);
+    animation.set_max_error(Some(40));
+    for pixels in &frames {
+        let frame = animation.frame(WIDTH, HEIGHT, &mut pixels.clone());
+        assert_eq!(frame.palette, None);
+        let mut with_palette = frame.clone();
+        with_palette.palette = Some(animation.palette().to_vec());
+        assert!(mean_squared_error(&with_palette, pixels) <= 40);
+    }
+}
+
+#[test]
+fn animation_local_palette_fallback() {
+    let reds: Vec<u8> = (0..=255u8).flat_map(|v| [v, 0, 0, 0xFF]).collect();
+    let blues: Vec<u8> = (0..=255u8).flat_map(|v| [0, 0, v, 0xFF]).collect();
+    let mut animation = AnimationPalette::new(MedianCut, FrameOptions::new(), &[&reds]);
+    assert_eq!(animation.palette().len(), 256 * 3);
+
+    // Without a limit the blues are mapped to dark reds
+    let frame = animation.frame(16, 16, &mut blues.clone());
+    assert_eq!(frame.palette, None);
+
+    animation.set_max_error(Some(100));
+    assert_eq!(animation.frame(16, 16, &mut reds.clone()).palette, None);
+    let frame = animation.frame(16, 16, &mut blues.clone());
+    assert_eq!(frame.palette.as_deref().map(<[u8]>::len), Some(256 * 3));
+    assert_eq!(frame.buffer, (0..=255u8).collect::<Vec<_>>());
+
+    // Transparency needs a local palette too if the sample had none
+    let mut transparent = reds.clone();
+    transparent[3] = 0;
+    let frame = animation.frame(16, 16, &mut transparent);
+    assert!(frame.palette.is_some());
+    assert_eq!(frame.buffer[0], frame.transparent.unwrap());
+}
+
+#[test]
+fn animation_unchanged_pixels_are_stable() {
+    let first = gray_ramp(32);
+    let mut second = first.clone();
+    second[40 * 4..60 * 4].fill(0x80);
+    let mut options = FrameOptions::new();
+    options.set_dither(Dither::Sierra);
+    let palette: Vec<u8> = (0..4u8)
+        .flat_map(|v| [v * 85, v * 85, v * 85, 0xFF])
+        .collect();
+    let mut animation = AnimationPalette::new(Octree, options, &[&palette]);
+    assert_eq!(animation.palette().len(), 4 * 3);
+    let previous = animation.frame(256, 32, &mut first.clone());
+    let next = animation.frame(256, 32, &mut second.clone());
+    for (n, (a, b)) in previous.buffer.iter().zip(&*next.buffer).enumerate() {
+        if first[n * 4..][..4] == second[n * 4..][..4] {
+            assert_eq!(a, b, "pixel {n}");
+        }
+    }
+}
+
+#[test]
+fn animation_try_frame_error() {
+    use gif::{EncodingError, EncodingFormatError};
+
+    let pixels = gray_ramp(2);
+    let mut animation = AnimationPalette::new(MedianCut, FrameOptions::new(), &[&pixels]);
+    let previous = animation.try_frame(256, 2, &mut pixels.clone()).unwrap();
+    let mut invalid = pixels.clone();
+    invalid[3] = 0;
+    assert!(matches!(
+        animation.try_frame(256, 3, &mut invalid),
+        Err(EncodingError::Format(EncodingFormatError::InvalidFrameSize))
+    ));
+    // The alpha options are not applied, and the animation continues
+    assert_eq!(invalid[3], 0);
+    let next = animation.try_frame(256, 2, &mut pixels.clone()).unwrap();
+    assert_eq!(next.buffer, previous.buffer);
+}
+
+#[test]
+fn alpha_threshold() {
+    let mut pixels: Vec<u8> = [0u8, 50, 100, 150, 200, 255]
+        .iter()
+        .flat_map(|&a| [10, 20, 30, a])
+        .collect();
+    let mut options = FrameOptions::new();
+    options.set_alpha_threshold(128);
+    let frame = options.quantize_rgba(6, 1, &mut pixels, &MedianCut);
+    let transparent = frame.transparent.unwrap();
+    let is_transparent: Vec<bool> = frame.buffer.iter().map(|&i| i == transparent).collect();
+    assert_eq!(is_transparent, [true, true, true, false, false, false]);
+    assert!(pixels.chunks_exact(4).all(|p| p[3] == 0 || p[3] == 0xFF));
+
+    // The default only treats zero alpha as transparent
+    let mut pixels: Vec<u8> = [0u8, 1].iter().flat_map(|&a| [10, 20, 30, a]).collect();
+    let frame = Frame::from_rgba_with_quantizer(2, 1, &mut pixels, &MedianCut);
+    assert_ne!(frame.buffer[1], frame.transparent.unwrap());
+}
+
+#[test]
+fn matte() {
+    let mut pixels = vec![0xFF, 0, 0, 0x80, 0xFF, 0, 0, 0xFF, 0, 0, 0, 0];
+    let mut options = FrameOptions::new();
+    options.set_matte(Some([0xFF, 0xFF, 0xFF]));
+    let frame = options.quantize_rgba(3, 1, &mut pixels, &MedianCut);
+    let palette = frame.palette.as_deref().unwrap();
+    let color = |n: usize| &palette[usize::from(frame.buffer[n]) * 3..][..3];
+    // Half transparent red over white, the opaque red is unchanged
+    assert_eq!(color(0), [0xFF, 0x7F, 0x7F]);
+    assert_eq!(color(1), [0xFF, 0, 0]);
+    assert_eq!(frame.buffer[2], frame.transparent.unwrap());
+}
+
+#[test]
+fn alpha_dither() {
+    let mut options = FrameOptions::new();
+    options.set_alpha_dither(true);
+    for (alpha, expected) in [(0, 256), (0x40, 192), (0x80, 128), (0xC0, 64), (0xFF, 0)] {
+        let mut pixels: Vec<u8> = (0..256).flat_map(|_| [10, 20, 30, alpha]).collect();
+        let frame = options.quantize_rgba(16, 16, &mut pixels, &MedianCut);
+        let transparent = frame
+            .buffer
+            .iter()
+            .filter(|&&i| Some(i) == frame.transparent)
+            .count();
+        assert_eq!(transparent, expected, "alpha {alpha}");
+    }
+}
+
+#[test]
+fn animation_alpha_options() {
+    let pixels: Vec<u8> = (0..64).flat_map(|n| [0xFF, 0, 0, n * 4]).collect();
+    let mut options = FrameOptions::new();
+    options.set_alpha_threshold(0x80);
+    options.set_matte(Some([0, 0, 0]));
+    let mut animation = AnimationPalette::new(MedianCut, options, &[&pixels]);
+    let transparent = animation.transparent().expect("transparent index");
+    let frame = animation.frame(8, 8, &mut pixels.clone());
+    assert_eq!(frame.palette, None);
+    for (n, &index) in frame.buffer.iter().enumerate() {
+        assert_eq!(index == transparent, n < 32, "pixel {n}");
+    }
+}
+
+#[test]
+fn rgba_formats() {
+    use gif::RgbaFormat;
+
+    let colors = |frame: &Frame<'_>| -> Vec<[u8; 3]> {
+        let palette = frame.palette.as_deref().unwrap();
+        frame
+            .buffer
+            .iter()
+            .map(|&i| {
+                let i = usize::from(i) * 3;
+                [palette[i], palette[i + 1], palette[i + 2]]
+            })
+            .collect()
+    };
+
+    let pixels = [3, 2, 1, 0xFF, 0x80, 0x40, 0, 0x80];
+    let frame = Frame::from_rgba_with_format(2, 1, &pixels, RgbaFormat::Bgra, &MedianCut);
+    assert_eq!(colors(&frame), [[1, 2, 3], [0, 0x40, 0x80]]);
+    let frame = Frame::from_rgba_with_format(2, 1, &pixels, RgbaFormat::Rgba, &MedianCut);
+    assert_eq!(colors(&frame), [[3, 2, 1], [0x80, 0x40, 0]]);
+    let frame =
+        Frame::from_rgba_with_format(2, 1, &pixels, RgbaFormat::PremultipliedRgba, &MedianCut);
+    assert_eq!(colors(&frame), [[3, 2, 1], [0xFF, 0x80, 0]]);
+    let frame =
+        Frame::from_rgba_with_format(2, 1, &pixels, RgbaFormat::PremultipliedBgra, &MedianCut);
+    assert_eq!(colors(&frame), [[1, 2, 3], [0, 0x80, 0xFF]]);
+    assert_eq!(frame.transparent, None);
+}
+
+#[test]
+fn rgba16_formats() {
+    use gif::RgbaFormat;
+
+    let pixels: [u16; 12] = [
+        0xFFFF, 0x8080, 0x0101, 0xFFFF, // opaque
+        0x4000, 0x2000, 0x0000, 0x4000, // premultiplied by 1/4
+        0x1234, 0x5678, 0x9ABC, 0x0050, // alpha rounds to zero
+    ];
+    let frame = Frame::from_rgba16_with_format(3, 1, &pixels, RgbaFormat::Rgba, &Octree);
+    let palette = frame.palette.as_deref().unwrap();
+    let transparent = frame.transparent.unwrap();
+    assert_eq!(frame.buffer[2], transparent);
+    let color = |n: usize| &palette[usize::from(frame.buffer[n]) * 3..][..3];
+    assert_eq!(color(0), [0xFF, 0x80, 0x01]);
+    assert_eq!(color(1), [0x40, 0x20, 0x00]);
+
+    let frame =
+        Frame::from_rgba16_with_format(3, 1, &pixels, RgbaFormat::PremultipliedBgra, &Octree);
+    let palette = frame.palette.as_deref().unwrap();
+    let color = |n: usize| &palette[usize::from(frame.buffer[n]) * 3..][..3];
+    assert_eq!(color(0), [0x01, 0x80, 0xFF]);
+    assert_eq!(color(1), [0x00, 0x80, 0xFF]);
+    assert_eq!(frame.buffer[2], frame.transparent.unwrap());
+
+    assert!(matches!(
+        Frame::try_from_rgba16_with_format(2, 1, &pixels, RgbaFormat::Rgba, &Octree),
+        Err(gif::EncodingError::Format(
+            gif::EncodingFormatError::InvalidFrameSize
+        ))
+    ));
+}
@@ -228,3 +228,504 @@ fn palette_fail() {
         ))
     ));
 }
+
+#[test]
+fn pixel_aspect_ratio() {
+    use gif::PixelAspectRatio;
+
+    let decoder = Decoder::new(&include_bytes!("samples/2x2.gif")[..]).unwrap();
+    assert_eq!(decoder.pixel_aspect_ratio(), PixelAspectRatio::UNSPECIFIED);
+    assert_eq!(decoder.pixel_aspect_ratio().to_f32(), None);
+
+    let ratio = PixelAspectRatio::from_f32(0.5).unwrap();
+    let mut builder = gif::EncoderBuilder::new(1, 1);
+    builder.set_global_palette(&[0; 6]);
+    builder.set_pixel_aspect_ratio(ratio);
+    let mut encoder = builder.build(vec![]).unwrap();
+    encoder
+        .write_frame(&Frame::from_indexed_pixels(1, 1, vec![0], None))
+        .unwrap();
+    let data = encoder.into_inner().unwrap();
+    assert_eq!(data[12], 17);
+
+    let decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(decoder.pixel_aspect_ratio(), ratio);
+    assert_eq!(decoder.pixel_aspect_ratio().to_f32(), Some(0.5));
+}
+
+#[test]
+fn pixel_aspect_ratio_conversion() {
+    use gif::PixelAspectRatio;
+
+    assert_eq!(PixelAspectRatio::SQUARE.to_f32(), Some(1.0));
+    assert_eq!(
+        PixelAspectRatio::from_f32(1.0),
+        Some(PixelAspectRatio::SQUARE)
+    );
+    assert_eq!(PixelAspectRatio(1).to_f32(), Some(0.25));
+    assert_eq!(PixelAspectRatio(255).to_f32(), Some(4.21875));
+    assert_eq!(PixelAspectRatio::from_f32(0.25), Some(PixelAspectRatio(1)));
+    assert_eq!(PixelAspectRatio::from_f32(4.0), Some(PixelAspectRatio(241)));
+    assert_eq!(PixelAspectRatio::from_f32(0.2), None);
+    assert_eq!(PixelAspectRatio::from_f32(4.3), None);
+    assert_eq!(PixelAspectRatio::from_f32(f32::NAN), None);
+    for n in 1..=255 {
+        let ratio = PixelAspectRatio(n);
+        assert_eq!(
+            PixelAspectRatio::from_f32(ratio.to_f32().unwrap()),
+            Some(ratio)
+        );
+    }
+}
+
+/// Returns the extension block starting with the application identifier, including its framing.
+fn application_extension<'a>(image: &'a [u8], identifier: &[u8; 11]) -> &'a [u8] {
+    let start = image
+        .windows(14)
+        .position(|w| w[..3] == [0x21, 0xFF, 11] && w[3..] == identifier[..])
+        .expect("extension not found");
+    let mut end = start + 14;
+    while image[end] != 0 {
+        end += usize::from(image[end]) + 1;
+    }
+    &image[start..=end]
+}
+
+fn metadata_round_trip(original: &[u8]) -> Vec<u8> {
+    let mut decoder = Decoder::new(original).unwrap();
+    let mut encoder = Encoder::new(
+        vec![],
+        decoder.width(),
+        decoder.height(),
+        decoder.global_palette().unwrap_or_default(),
+    )
+    .unwrap();
+    encoder.set_repeat(decoder.repeat()).unwrap();
+    if let Some(xmp) = decoder.xmp_metadata() {
+        encoder.write_xmp_metadata(xmp).unwrap();
+    }
+    if let Some(icc) = decoder.icc_profile() {
+        encoder.write_icc_profile(icc).unwrap();
+    }
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        encoder.write_frame(frame).unwrap();
+    }
+    let encoded = encoder.into_inner().unwrap();
+
+    let mut original = Decoder::new(original).unwrap();
+    let mut decoder = Decoder::new(&encoded[..]).unwrap();
+    assert_eq!(decoder.xmp_metadata(), original.xmp_metadata());
+    assert_eq!(decoder.icc_profile(), original.icc_profile());
+    while let Some(frame) = original.read_next_frame().unwrap() {
+        let frame = frame.clone();
+        assert_eq!(
+            decoder.read_next_frame().unwrap().unwrap().buffer,
+            frame.buffer
+        );
+    }
+    assert!(decoder.read_next_frame().unwrap().is_none());
+    encoded
+}
+
+#[test]
+fn xmp_round_trip() {
+    const ORIGINAL: &[u8] = include_bytes!("samples/beacon_xmp.gif");
+    let encoded = metadata_round_trip(ORIGINAL);
+    assert_eq!(
+        application_extension(&encoded, b"XMP DataXMP"),
+        application_extension(ORIGINAL, b"XMP DataXMP")
+    );
+}
+
+#[test]
+fn icc_round_trip() {
+    const ORIGINAL: &[u8] = include_bytes!("samples/beacon_icc.gif");
+    let encoded = metadata_round_trip(ORIGINAL);
+    assert_eq!(
+        application_extension(&encoded, b"ICCRGBG1012"),
+        application_extension(ORIGINAL, b"ICCRGBG1012")
+    );
+}
+
+#[test]
+fn xmp_with_nul_is_rejected() {
+    let mut encoder = Encoder::new(vec![], 1, 1, &[0; 6]).unwrap();
+    assert!(matches!(
+        encoder.write_xmp_metadata(b"<x:xmpmeta>\0</x:xmpmeta>"),
+        Err(gif::EncodingError::Format(
+            gif::EncodingFormatError::InvalidXmpMetadata
+        ))
+    ));
+}
+
+#[test]
+fn try_from_constructors() {
+    use gif::quantize::MedianCut;
+    use gif::{EncodingError, EncodingFormatError};
+
+    let invalid_size = |result: Result<Frame<'static>, EncodingError>| {
+        matches!(
+            result,
+            Err(EncodingError::Format(EncodingFormatError::InvalidFrameSize))
+        )
+    };
+    assert!(invalid_size(Frame::try_from_indexed_pixels(
+        2,
+        2,
+        vec![0; 3],
+        None
+    )));
+    assert!(invalid_size(Frame::try_from_palette_pixels(
+        2,
+        2,
+        vec![0; 5],
+        vec![0; 3],
+        None
+    )));
+    assert!(invalid_size(Frame::try_from_grayscale_with_alpha(
+        2, 2, &[0; 6]
+    )));
+    assert!(invalid_size(Frame::try_from_rgba_with_quantizer(
+        2,
+        2,
+        &mut [0; 15],
+        &MedianCut
+    )));
+    assert!(invalid_size(Frame::try_from_rgb_with_quantizer(
+        0xFFFF, 0xFFFF, &[0; 12], &MedianCut
+    )));
+    assert!(matches!(
+        Frame::try_from_palette_pixels(1, 1, vec![0], vec![0; 257 * 3], None),
+        Err(EncodingError::Format(EncodingFormatError::TooManyColors))
+    ));
+
+    let frame = Frame::try_from_rgb_with_quantizer(2, 1, &[1, 2, 3, 4, 5, 6], &MedianCut).unwrap();
+    assert_eq!(frame.palette.as_deref(), Some(&[1, 2, 3, 4, 5, 6][..]));
+    let frame = Frame::try_from_palette_pixels(2, 1, vec![0, 1], vec![0; 6], Some(1)).unwrap();
+    assert_eq!(frame.transparent, Some(1));
+}
+
+#[test]
+#[cfg(feature = "color_quant")]
+fn try_from_rgb_speed() {
+    use gif::{EncodingError, EncodingFormatError};
+
+    for speed in [0, 31] {
+        assert!(matches!(
+            Frame::try_from_rgb_speed(1, 1, &[0; 3], speed),
+            Err(EncodingError::Format(EncodingFormatError::InvalidSpeed))
+        ));
+    }
+    assert!(matches!(
+        Frame::try_from_rgba(1, 1, &mut [0; 3]),
+        Err(EncodingError::Format(EncodingFormatError::InvalidFrameSize))
+    ));
+    assert!(Frame::try_from_rgb(1, 1, &[0; 3]).is_ok());
+}
+
+#[test]
+fn encoder_builder() {
+    use gif::{EncoderBuilder, PixelAspectRatio};
+
+    let palette: Vec<u8> = (0..16).flat_map(|i| [i * 16; 3]).collect();
+    let mut builder = EncoderBuilder::new(2, 2);
+    builder.set_global_palette(&palette);
+    builder.set_background_color(3);
+    builder.set_color_resolution(8);
+    builder.set_sorted(true);
+    builder.set_pixel_aspect_ratio(PixelAspectRatio::SQUARE);
+    let mut encoder = builder.build(vec![]).unwrap();
+    encoder
+        .write_frame(&Frame::from_indexed_pixels(2, 2, vec![0, 1, 2, 3], None))
+        .unwrap();
+    let data = encoder.into_inner().unwrap();
+    assert_eq!(&data[..6], b"GIF87a");
+    // Global palette of 16 entries, 8 bits per color and sorted
+    assert_eq!(data[10], 0b1111_1011);
+    assert_eq!(data[11], 3);
+
+    let decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(decoder.bg_color(), Some(3));
+    assert_eq!(decoder.pixel_aspect_ratio(), PixelAspectRatio::SQUARE);
+    assert_eq!(decoder.global_palette(), Some(&palette[..]));
+
+    for bits in [0, 9] {
+        let mut builder = EncoderBuilder::new(2, 2);
+        builder.set_color_resolution(bits);
+        assert!(matches!(
+            builder.build(vec![]),
+            Err(gif::EncodingError::Format(
+                gif::EncodingFormatError::InvalidColorResolution
+            ))
+        ));
+    }
+
+    // The defaults with GIF89a match `Encoder::new`
+    let mut builder = EncoderBuilder::new(2, 2);
+    builder.set_global_palette(&palette);
+    builder.set_version(gif::Version::V89a);
+    let encoder = Encoder::new(vec![], 2, 2, &palette).unwrap();
+    assert_eq!(
+        builder.build(vec![]).unwrap().into_inner().unwrap(),
+        encoder.into_inner().unwrap()
+    );
+}
+
+#[test]
+fn encoder_builder_automatic_version() {
+    use gif::{EncoderBuilder, Repeat, Version};
+
+    let mut builder = EncoderBuilder::new(2, 2);
+    builder.set_global_palette(&[0, 0, 0, 0xFF, 0xFF, 0xFF]);
+    let frame = Frame::from_indexed_pixels(2, 2, vec![0, 1, 1, 0], None);
+    let mut delayed = frame.clone();
+    delayed.delay = 10;
+
+    // Without extensions the output is held back and written as GIF87a
+    let mut encoder = builder.build(vec![]).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    assert!(encoder.get_ref().is_empty());
+    let data = encoder.into_inner().unwrap();
+    assert_eq!(&data[..6], b"GIF87a");
+    assert!(!data[19..].contains(&0x21));
+    let mut decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(
+        &*decoder.read_next_frame().unwrap().unwrap().buffer,
+        &*frame.buffer
+    );
+    assert!(decoder.read_next_frame().unwrap().is_some());
+
+    // The first extension switches to GIF89a and writes everything
+    let mut encoder = builder.build(vec![]).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    encoder.write_frame(&delayed).unwrap();
+    assert_eq!(&encoder.get_ref()[..6], b"GIF89a");
+    let data = encoder.into_inner().unwrap();
+    let mut decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(decoder.read_next_frame().unwrap().unwrap().delay, 0);
+    assert_eq!(decoder.read_next_frame().unwrap().unwrap().delay, 10);
+
+    let mut encoder = builder.build(vec![]).unwrap();
+    encoder.set_repeat(Repeat::Infinite).unwrap();
+    assert_eq!(&encoder.get_ref()[..6], b"GIF89a");
+    encoder.write_frame(&frame).unwrap();
+    let data = encoder.into_inner().unwrap();
+    let decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(decoder.repeat(), Repeat::Infinite);
+
+    // An explicit version is written right away
+    builder.set_version(Version::V89a);
+    let encoder = builder.build(vec![]).unwrap();
+    assert_eq!(&encoder.get_ref()[..6], b"GIF89a");
+}
+
+#[test]
+fn encoder_builder_gif87a() {
+    use gif::streaming_decoder::{OutputBuffer, StreamingDecoder};
+    use gif::{EncoderBuilder, EncodingError, EncodingFormatError, Repeat, Version};
+
+    let mut builder = EncoderBuilder::new(2, 2);
+    builder.set_global_palette(&[0, 0, 0, 0xFF, 0xFF, 0xFF]);
+    builder.set_version(Version::V87a);
+    let mut encoder = builder.build(vec![]).unwrap();
+    // Nothing is written for a finite number of 0 repetitions
+    encoder.set_repeat(Repeat::Finite(0)).unwrap();
+    let frame = Frame::from_indexed_pixels(2, 2, vec![0, 1, 1, 0], None);
+    encoder.write_frame(&frame).unwrap();
+
+    let requires_gif89a = |result| {
+        matches!(
+            result,
+            Err(EncodingError::Format(EncodingFormatError::RequiresGif89a))
+        )
+    };
+    assert!(requires_gif89a(encoder.set_repeat(Repeat::Infinite)));
+    assert!(requires_gif89a(encoder.write_comment(b"comment")));
+    assert!(requires_gif89a(encoder.write_xmp_metadata(b"<xmp/>")));
+    let mut delayed = frame.clone();
+    delayed.delay = 10;
+    assert!(requires_gif89a(encoder.write_frame(&delayed)));
+    let mut transparent = frame.clone();
+    transparent.transparent = Some(0);
+    assert!(requires_gif89a(encoder.write_frame(&transparent)));
+    let mut dispose = frame.clone();
+    dispose.dispose = gif::DisposalMethod::Background;
+    assert!(requires_gif89a(encoder.write_frame(&dispose)));
+
+    let data = encoder.into_inner().unwrap();
+    assert_eq!(&data[..6], b"GIF87a");
+    // No extension introducer
+    assert!(!data[19..].contains(&0x21));
+    let mut decoder = StreamingDecoder::new();
+    decoder.update(&data, &mut OutputBuffer::None).unwrap();
+    assert_eq!(decoder.version(), Version::V87a);
+    let mut decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(
+        &*decoder.read_next_frame().unwrap().unwrap().buffer,
+        [0, 1, 1, 0]
+    );
+    assert!(decoder.read_next_frame().unwrap().is_none());
+}
+
+#[test]
+fn frame_writer() {
+    let (width, height) = (300, 200);
+    let pixels: Vec<u8> = (0..width * height)
+        .map(|i| (i * 7 % 251 + i / 1000) as u8)
+        .collect();
+    let palette: Vec<u8> = (0..=255).flat_map(|i| [i; 3]).collect();
+    let frame = Frame {
+        width: width as u16,
+        height: height as u16,
+        palette: Some(palette),
+        ..Frame::default()
+    };
+
+    let mut encoder = Encoder::new(vec![], width as u16, height as u16, &[]).unwrap();
+    let mut writer = encoder.frame_writer(&frame).unwrap();
+    let mut rows = pixels.chunks(width * 3);
+    let first = rows.next().unwrap();
+    writer.write_row(&first[..width]).unwrap();
+    writer.write_rows(&first[width..]).unwrap();
+    for chunk in rows {
+        writer.write_rows(chunk).unwrap();
+    }
+    assert_eq!(writer.next_row(), None);
+    writer.finish().unwrap();
+    let data = encoder.into_inner().unwrap();
+
+    let mut decoder = Decoder::new(&data[..]).unwrap();
+    assert_eq!(*decoder.read_next_frame().unwrap().unwrap().buffer, pixels);
+    assert!(decoder.read_next_frame().unwrap().is_none());
+}
+
+#[test]
+fn frame_writer_interlaced() {
+    let (width, height) = (5u16, 11u16);
+    let pixels: Vec<u8> = (0..width * height).map(|i| (i % 4) as u8).collect();
+    let frame = Frame {
+        width,
+        height,
+        interlaced: true,
+        ..Frame::default()
+    };
+
+    let mut encoder = Encoder::new(vec![], width, height, &[0; 12]).unwrap();
+    let mut writer = encoder.frame_writer(&frame).unwrap();
+    let mut order = Vec::new();
+    while let Some(y) = writer.next_row() {
+        order.push(y);
+        let start = usize::from(y * width);
+        writer
+            .write_row(&pixels[start..start + usize::from(width)])
+            .unwrap();
+    }
+    writer.finish().unwrap();
+    assert_eq!(order, [0, 8, 4, 2, 6, 10, 1, 3, 5, 7, 9]);
+    let data = encoder.into_inner().unwrap();
+
+    let mut decoder = Decoder::new(&data[..]).unwrap();
+    let frame = decoder.read_next_frame().unwrap().unwrap();
+    assert!(!frame.interlaced);
+    assert_eq!(*frame.buffer, pixels);
+}
+
+#[test]
+fn frame_writer_errors() {
+    use gif::{EncodingError, EncodingFormatError};
+
+    let invalid_size = |result| {
+        matches!(
+            result,
+            Err(EncodingError::Format(EncodingFormatError::InvalidFrameSize))
+        )
+    };
+    let frame = Frame {
+        width: 2,
+        height: 2,
+        ..Frame::default()
+    };
+    let mut encoder = Encoder::new(vec![], 2, 2, &[0; 6]).unwrap();
+    let mut writer = encoder.frame_writer(&frame).unwrap();
+    assert!(invalid_size(writer.write_row(&[0])));
+    assert!(invalid_size(writer.write_rows(&[0; 6])));
+    writer.write_row(&[0, 1]).unwrap();
+    assert_eq!(writer.next_row(), Some(1));
+    assert!(matches!(
+        writer.write_row(&[1, 4]),
+        Err(EncodingError::Format(
+            EncodingFormatError::InvalidColorIndex
+        ))
+    ));
+    assert!(invalid_size(writer.finish()));
+
+    let mut missing_palette = Encoder::new(vec![], 2, 2, &[]).unwrap();
+    assert!(matches!(
+        missing_palette.frame_writer(&frame),
+        Err(EncodingError::Format(
+            EncodingFormatError::MissingColorPalette
+        ))
+    ));
+}
+
+#[test]
+fn frame_writer_dropped() {
+    use gif::{EncodingError, EncodingFormatError};
+
+    let unfinished = |result| {
+        matches!(
+            result,
+            Err(EncodingError::Format(EncodingFormatError::UnfinishedFrame))
+        )
+    };
+    let frame = Frame::from_indexed_pixels(2, 2, vec![0, 1, 1, 0], None);
+    let mut encoder = Encoder::new(vec![], 2, 2, &[0; 6]).unwrap();
+    let mut writer = encoder.frame_writer(&frame).unwrap();
+    writer.write_row(&[0, 1]).unwrap();
+    drop(writer);
+    assert!(unfinished(encoder.write_frame(&frame)));
+    assert!(unfinished(encoder.frame_writer(&frame).map(drop)));
+    assert!(unfinished(encoder.into_inner().map(drop)));
+
+    // No trailer is written when the encoder is dropped
+    let mut output = vec![];
+    let mut encoder = Encoder::new(&mut output, 2, 2, &[0; 6]).unwrap();
+    drop(encoder.frame_writer(&frame).unwrap());
+    let len = encoder.get_ref().len();
+    drop(encoder);
+    assert_eq!(output.len(), len);
+}
+
+#[test]
+fn interlaced_frame() {
+    let (width, height) = (3u16, 10u16);
+    let pixels: Vec<u8> = (0..width * height).map(|i| (i / 3) as u8).collect();
+    let palette: Vec<u8> = (0..16).flat_map(|i| [i * 16; 3]).collect();
+    let mut frame = Frame::from_indexed_pixels(width, height, pixels.clone(), None);
+    frame.interlaced = true;
+
+    let mut encoder = Encoder::new(vec![], width, height, &palette).unwrap();
+    encoder.write_frame(&frame).unwrap();
+    // The same rows in the order of the interlacing passes
+    let passes = [0, 8, 4, 2, 6, 1, 3, 5, 7, 9];
+    let mut pre_interlaced = frame.clone();
+    pre_interlaced.buffer = passes.iter().flat_map(|&row| [row; 3]).collect();
+    encoder.write_pre_interlaced_frame(&pre_interlaced).unwrap();
+    let mut pre_encoded = frame.clone();
+    pre_encoded.make_lzw_pre_encoded();
+    let mut try_pre_encoded = frame.clone();
+    try_pre_encoded.try_make_lzw_pre_encoded().unwrap();
+    assert_eq!(try_pre_encoded.buffer, pre_encoded.buffer);
+    encoder.write_lzw_pre_encoded_frame(&pre_encoded).unwrap();
+    let data = encoder.into_inner().unwrap();
+
+    let mut decoder = Decoder::new(&data[..]).unwrap();
+    for i in 0..3 {
+        assert!(decoder.next_frame_info().unwrap().unwrap().interlaced);
+        let mut buffer = vec![0; decoder.buffer_size()];
+        decoder.read_into_buffer(&mut buffer).unwrap();
+        assert_eq!(buffer, pixels, "frame {i}");
+    }
+    assert!(decoder.next_frame_info().unwrap().is_none());
+}
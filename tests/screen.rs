@@ -0,0 +1,210 @@
+#![cfg(feature = "std")]
+
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, Encoder, Frame, Screen};
+
+fn composite(image: &[u8], color: ColorOutput) -> Vec<Vec<u8>> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(color);
+    let mut decoder = options.read_info(image).unwrap();
+    let mut screen = Screen::new_decoder(&decoder);
+    let mut canvases = vec![];
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        screen.blit_frame(frame).unwrap();
+        assert_eq!(
+            screen.pixels_rgba().len(),
+            usize::from(screen.width()) * usize::from(screen.height()) * 4
+        );
+        canvases.push(screen.pixels_rgba().to_vec());
+    }
+    canvases
+}
+
+/// CRC-32 of all canvases.
+fn crc32(canvases: &[Vec<u8>]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in canvases.iter().flatten() {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[test]
+fn indexed_and_rgba_agree() {
+    for (image, frames, crc) in [
+        (
+            &include_bytes!("samples/moon_impact.gif")[..],
+            14,
+            0x915b_73be,
+        ),
+        (&include_bytes!("samples/anim-gr.gif")[..], 2, 0x1162_2d9e),
+        (
+            &include_bytes!("samples/gifplayer-muybridge.gif")[..],
+            380,
+            0x6ac1_643a,
+        ),
+        (
+            &include_bytes!("samples/interlaced.gif")[..],
+            1,
+            0x7e17_e5cc,
+        ),
+    ] {
+        let indexed = composite(image, ColorOutput::Indexed);
+        let rgba = composite(image, ColorOutput::RGBA);
+        assert_eq!(indexed.len(), frames);
+        assert_eq!(crc32(&indexed), crc);
+        assert_eq!(indexed, rgba);
+    }
+}
+
+#[test]
+fn indexed_canvas() {
+    let image: &[u8] = include_bytes!("samples/sample_1.gif");
+    let mut decoder = DecodeOptions::new().read_info(image).unwrap();
+    let mut screen = Screen::new_decoder(&decoder);
+    let frame = decoder.read_next_frame().unwrap().unwrap();
+    screen.blit_frame(frame).unwrap();
+    assert_eq!(screen.pixels_indexed(), Some(&*frame.buffer));
+
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let decoder = options.read_info(image).unwrap();
+    assert_eq!(Screen::new_decoder(&decoder).pixels_indexed(), None);
+}
+
+/// A 4x1 screen with a 2x1 frame drawn at its left edge, followed by a 1x1 frame at x = 3.
+fn disposal_image(dispose: DisposalMethod) -> Vec<u8> {
+    let palette = [0, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0, 0, 0, 0xFF];
+    let mut encoder = Encoder::new(vec![], 4, 1, &palette).unwrap();
+    let mut first = Frame::from_indexed_pixels(4, 1, vec![1, 1, 1, 1], None);
+    first.dispose = DisposalMethod::Keep;
+    encoder.write_frame(&first).unwrap();
+    let mut second = Frame::from_indexed_pixels(2, 1, vec![2, 0], Some(0));
+    second.dispose = dispose;
+    encoder.write_frame(&second).unwrap();
+    let mut third = Frame::from_indexed_pixels(1, 1, vec![3], None);
+    third.left = 3;
+    encoder.write_frame(&third).unwrap();
+    encoder.into_inner().unwrap()
+}
+
+#[test]
+fn disposal_methods() {
+    for (dispose, expected) in [
+        (DisposalMethod::Keep, [2, 1, 1, 3]),
+        (DisposalMethod::Any, [2, 1, 1, 3]),
+        (DisposalMethod::Background, [0, 0, 1, 3]),
+        (DisposalMethod::Previous, [1, 1, 1, 3]),
+    ] {
+        let image = disposal_image(dispose);
+        let mut decoder = DecodeOptions::new().read_info(&image[..]).unwrap();
+        let mut screen = Screen::new_decoder(&decoder);
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            screen.blit_frame(frame).unwrap();
+        }
+        assert_eq!(screen.pixels_indexed(), Some(&expected[..]), "{dispose:?}");
+    }
+}
+
+#[test]
+fn transparent_background() {
+    let image = disposal_image(DisposalMethod::Background);
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(&image[..]).unwrap();
+    let mut screen = Screen::new_decoder(&decoder);
+    assert!(screen.pixels_rgba().iter().all(|&b| b == 0));
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        screen.blit_frame(frame).unwrap();
+    }
+    #[rustfmt::skip]
+    assert_eq!(screen.pixels_rgba(), &[
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+        0xFF, 0, 0, 0xFF,
+        0, 0, 0xFF, 0xFF,
+    ]);
+}
+
+#[test]
+fn opaque_background() {
+    let image = disposal_image(DisposalMethod::Background);
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    let mut decoder = options.read_info(&image[..]).unwrap();
+    let mut screen = Screen::new_decoder(&decoder);
+    screen.set_bg_color(decoder.bg_color());
+    assert!(screen.pixels_rgba().chunks(4).all(|p| p == [0, 0, 0, 0xFF]));
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        screen.blit_frame(frame).unwrap();
+    }
+    #[rustfmt::skip]
+    assert_eq!(screen.pixels_rgba(), &[
+        0, 0, 0, 0xFF,
+        0, 0, 0, 0xFF,
+        0xFF, 0, 0, 0xFF,
+        0, 0, 0xFF, 0xFF,
+    ]);
+}
+
+#[test]
+fn frame_outside_of_screen() {
+    let mut screen = Screen::new(2, 2, ColorOutput::Indexed, Some(&[0; 6]));
+    let mut frame = Frame::from_indexed_pixels(3, 3, vec![1; 9], None);
+    frame.left = 1;
+    frame.top = 1;
+    screen.blit_frame(&frame).unwrap();
+    assert_eq!(screen.pixels_indexed(), Some(&[0, 0, 0, 1][..]));
+
+    frame.top = 5;
+    screen.blit_frame(&frame).unwrap();
+    assert_eq!(screen.pixels_indexed(), Some(&[0, 0, 0, 1][..]));
+
+    frame.buffer = vec![1; 8].into();
+    assert!(screen.blit_frame(&frame).is_err());
+}
+
+#[test]
+fn plain_text() {
+    let mut screen = Screen::new(
+        20,
+        10,
+        ColorOutput::Indexed,
+        Some(&[0, 0, 0, 0xFF, 0xFF, 0xFF]),
+    );
+    screen.draw_plain_text(&gif::PlainText {
+        left: 1,
+        top: 1,
+        width: 16,
+        height: 8,
+        cell_width: 8,
+        cell_height: 8,
+        foreground: 1,
+        background: 0,
+        text: b"AAA".to_vec(),
+    });
+    let indexed = screen.pixels_indexed().unwrap();
+    let row = |y: usize| &indexed[y * 20..][..20];
+    // The first row of 'A' is 0x0C, setting the third and fourth pixel of each glyph
+    #[rustfmt::skip]
+    assert_eq!(row(1), [
+        0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 1, 1, 0, 0, 0, 0, 0, 0, 0,
+    ]);
+    // The last row of 'A' is empty
+    assert!(row(8).iter().all(|&i| i == 0));
+    assert_eq!(&screen.pixels_rgba()[(20 + 3) * 4..][..4], [0xFF; 4]);
+}
+
+#[test]
+fn color_outputs_agree() {
+    for image in [
+        &include_bytes!("samples/moon_impact.gif")[..],
+        &include_bytes!("samples/alpha_gif_a.gif")[..],
+    ] {
+        let rgba = composite(image, ColorOutput::RGBA);
+        assert_eq!(composite(image, ColorOutput::BGRA), rgba);
+        assert_eq!(composite(image, ColorOutput::PremultipliedRGBA), rgba);
+    }
+}
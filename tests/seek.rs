@@ -0,0 +1,143 @@
+#![cfg(feature = "std")]
+
+use gif::{ColorOutput, DecodeOptions, DisposalMethod, Encoder, Frame, Screen};
+use std::io::Cursor;
+
+const IMAGES: [&[u8]; 4] = [
+    include_bytes!("samples/moon_impact.gif"),
+    include_bytes!("samples/anim-gr.gif"),
+    include_bytes!("samples/gifplayer-muybridge.gif"),
+    include_bytes!("samples/interlaced.gif"),
+];
+
+fn decoder(image: &[u8]) -> gif::Decoder<Cursor<&[u8]>> {
+    let mut options = DecodeOptions::new();
+    options.set_color_output(ColorOutput::RGBA);
+    options.read_info(Cursor::new(image)).unwrap()
+}
+
+#[test]
+fn seek_matches_sequential_decoding() {
+    for image in IMAGES {
+        let mut sequential = decoder(image);
+        let mut frames = vec![];
+        while let Some(frame) = sequential.read_next_frame().unwrap() {
+            frames.push(frame.clone());
+        }
+
+        let mut seeking = decoder(image);
+        let index = seeking.frame_index().unwrap();
+        assert_eq!(index.len(), frames.len());
+        assert_eq!(
+            index.frames()[0].has_local_palette,
+            frames[0].palette.is_some()
+        );
+        for n in (0..frames.len()).rev() {
+            seeking.seek_to_frame(n).unwrap();
+            let frame = seeking.read_next_frame().unwrap().unwrap();
+            assert_eq!(frame.buffer, frames[n].buffer);
+            assert_eq!(frame.delay, frames[n].delay);
+            assert_eq!(frame.dispose, frames[n].dispose);
+            assert_eq!(frame.transparent, frames[n].transparent);
+            assert_eq!(frame.left, frames[n].left);
+            assert_eq!(frame.top, frames[n].top);
+        }
+        seeking.seek_to_frame(frames.len()).unwrap();
+        assert!(seeking.read_next_frame().unwrap().is_none());
+        assert!(seeking.seek_to_frame(frames.len() + 1).is_err());
+    }
+}
+
+/// Checks that compositing from the keyframe gives the same canvas as sequential compositing.
+fn check_keyframes(image: &[u8], frames: impl Fn(usize) -> bool) {
+    let mut sequential = decoder(image);
+    let mut screen = Screen::new_decoder(&sequential);
+    let mut canvases = vec![];
+    while let Some(frame) = sequential.read_next_frame().unwrap() {
+        screen.blit_frame(frame).unwrap();
+        canvases.push(screen.pixels_rgba().to_vec());
+    }
+
+    let mut seeking = decoder(image);
+    let index = seeking.frame_index().unwrap().clone();
+    for (n, entry) in index
+        .frames()
+        .iter()
+        .enumerate()
+        .filter(|(n, _)| frames(*n))
+    {
+        assert!(entry.keyframe <= n);
+        seeking.seek_to_frame(entry.keyframe).unwrap();
+        let mut screen = Screen::new_decoder(&seeking);
+        for _ in entry.keyframe..=n {
+            screen
+                .blit_frame(seeking.read_next_frame().unwrap().unwrap())
+                .unwrap();
+        }
+        assert_eq!(screen.pixels_rgba(), canvases[n], "frame {n}");
+    }
+}
+
+#[test]
+fn composite_from_keyframe() {
+    for image in IMAGES {
+        let len = decoder(image).frame_index().unwrap().len();
+        check_keyframes(image, |n| n == len / 2 || n == len - 1);
+    }
+}
+
+#[test]
+fn keyframes() {
+    let palette = [0, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0, 0, 0, 0xFF];
+    let mut encoder = Encoder::new(vec![], 4, 1, &palette).unwrap();
+    let full = |color, transparent, dispose| {
+        let mut frame = Frame::from_indexed_pixels(4, 1, vec![color; 4], transparent);
+        frame.dispose = dispose;
+        frame
+    };
+    let pixel = |left, color| {
+        let mut frame = Frame::from_indexed_pixels(1, 1, vec![color], None);
+        frame.left = left;
+        frame
+    };
+    encoder
+        .write_frame(&full(1, None, DisposalMethod::Keep))
+        .unwrap();
+    encoder.write_frame(&pixel(1, 2)).unwrap();
+    // Independent, but restores the canvas that depends on the first frame
+    encoder
+        .write_frame(&full(3, None, DisposalMethod::Previous))
+        .unwrap();
+    encoder.write_frame(&pixel(2, 0)).unwrap();
+    // Clears the whole canvas
+    encoder
+        .write_frame(&full(2, Some(0), DisposalMethod::Background))
+        .unwrap();
+    encoder.write_frame(&pixel(3, 3)).unwrap();
+    let image = encoder.into_inner().unwrap();
+
+    let index = decoder(&image).frame_index().unwrap().clone();
+    assert_eq!(
+        index
+            .frames()
+            .iter()
+            .map(|f| f.keyframe)
+            .collect::<Vec<_>>(),
+        [0, 0, 2, 0, 0, 5]
+    );
+    check_keyframes(&image, |_| true);
+}
+
+#[test]
+fn index_keeps_position() {
+    let image = IMAGES[0];
+    let mut sequential = decoder(image);
+    sequential.read_next_frame().unwrap().unwrap();
+    let second = sequential.read_next_frame().unwrap().unwrap().clone();
+
+    let mut decoder = decoder(image);
+    decoder.read_next_frame().unwrap().unwrap();
+    assert!(decoder.frame_index().unwrap().len() > 2);
+    let frame = decoder.read_next_frame().unwrap().unwrap();
+    assert_eq!(frame.buffer, second.buffer);
+}